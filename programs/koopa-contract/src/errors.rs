@@ -1,73 +1,225 @@
-use anchor_lang::prelude::*;
-
-#[error_code]
-pub enum KooPaaError {
-    #[msg("You have already claimed your payout")]
-    AlreadyClaimed,
-
-    #[msg("Not all participants have contributed yet")]
-    NotAllContributed,
-
-    #[msg("Contribution amount must be greater than zero")]
-    InvalidContributionAmount,
-
-    #[msg("Interval must be between 1 and 90 days")]
-    InvalidInterval,
-
-    #[msg("Number of participants must be between 3 and 20")]
-    InvalidParticipantCount,
-
-    #[msg("Group name is too long (maximum 50 characters)")]
-    NameTooLong,
-
-    #[msg("Group has already started")]
-    GroupAlreadyStarted,
-
-    #[msg("Group is already closed")]
-    GroupAlreadyClosed,
-
-    #[msg("You have already joined this group")]
-    AlreadyJoined,
-
-    #[msg("Security Deposit must be greater than 0")]
-    InvalidSecurityDeposit,
-
-    #[msg("Only admin can update global state")]
-    OnlyAdminCanUpdate,
-
-    #[msg("You have already voted to close this group")]
-    AlreadyVotedToClose,
-
-    #[msg("You are not a participant in this group")]
-    NotParticipant,
-
-    #[msg("Group has not started yet")]
-    GroupNotStarted,
-
-    #[msg("Group has completed all rounds")]
-    GroupCompleted,
-
-    #[msg("You are not a participant in this group")]
-    NotAParticipant,
-
-    #[msg("You cannot contribute to this round")]
-    CannotContributeToThisRound,
-
-    #[msg("Interval has not passed yet")]
-    IntervalNotPassed,
-
-    #[msg("Insufficient funds in token account")]
-    InsufficientFunds,
-
-    #[msg("Fee percentage must be between 0 and 100")]
-    InvalidFeePercentage,
-
-    #[msg("You have already contributed to this round")]
-    AlreadyContributed,
-
-    #[msg("You are not the recipient for this round")]
-    NotCurrentRecipient,
-
-    #[msg("Payout period has not yet arrived")]
-    PayoutNotYetDue,
-}
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum KooPaaError {
+    // No longer raised, kept so the error codes of later variants stay the same
+    #[msg("You have already claimed your payout")]
+    AlreadyClaimed,
+
+    #[msg("Not all participants have contributed yet")]
+    NotAllContributed,
+
+    #[msg("Contribution amount must be greater than zero")]
+    InvalidContributionAmount,
+
+    #[msg("Interval is outside the allowed range")]
+    InvalidInterval,
+
+    #[msg("Number of participants is outside the allowed range")]
+    InvalidParticipantCount,
+
+    #[msg("Group name is too long (maximum 32 bytes)")]
+    NameTooLong,
+
+    #[msg("Group has already started")]
+    GroupAlreadyStarted,
+
+    #[msg("Group is already closed")]
+    GroupAlreadyClosed,
+
+    #[msg("You have already joined this group")]
+    AlreadyJoined,
+
+    #[msg("Security Deposit must be greater than 0")]
+    InvalidSecurityDeposit,
+
+    #[msg("Only admin can update global state")]
+    OnlyAdminCanUpdate,
+
+    #[msg("You have already voted to close this group")]
+    AlreadyVotedToClose,
+
+    #[msg("You are not a participant in this group")]
+    NotParticipant,
+
+    #[msg("Group has not started yet")]
+    GroupNotStarted,
+
+    #[msg("Group has completed all rounds")]
+    GroupCompleted,
+
+    // These three are no longer raised either
+    #[msg("You are not a participant in this group")]
+    NotAParticipant,
+
+    #[msg("You cannot contribute to this round")]
+    CannotContributeToThisRound,
+
+    #[msg("Interval has not passed yet")]
+    IntervalNotPassed,
+
+    #[msg("Insufficient funds in token account")]
+    InsufficientFunds,
+
+    #[msg("Fee percentage must be between 0 and 100")]
+    InvalidFeePercentage,
+
+    #[msg("You have already contributed to this round")]
+    AlreadyContributed,
+
+    #[msg("You are not the recipient for this round")]
+    NotCurrentRecipient,
+
+    #[msg("Payout period has not yet arrived")]
+    PayoutNotYetDue,
+
+    #[msg("Group must be closed or fully paid out before deposits can be withdrawn")]
+    GroupNotSettled,
+
+    #[msg("You have already withdrawn your security deposit")]
+    AlreadyRefunded,
+
+    #[msg("Group limits are inconsistent")]
+    InvalidGroupLimits,
+
+    #[msg("Only the proposed admin can accept the admin role")]
+    NotPendingAdmin,
+
+    #[msg("Only the admin or treasury multisig can withdraw fees")]
+    UnauthorizedTreasuryWithdrawal,
+
+    #[msg("Withdrawal amount exceeds the accrued revenue")]
+    WithdrawalExceedsRevenue,
+
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    #[msg("Token mint does not match the group's mint")]
+    InvalidMint,
+
+    #[msg("Amount received after transfer fees does not cover the contribution")]
+    TransferFeeShortfall,
+
+    #[msg("Vault is not owned by the signer")]
+    InvalidVaultAuthority,
+
+    #[msg("Only the group creator can perform this action")]
+    OnlyCreator,
+
+    #[msg("This action does not apply to the group's payout order")]
+    PayoutOrderMismatch,
+
+    #[msg("A member is listed more than once in the payout order")]
+    DuplicatePayoutPosition,
+
+    #[msg("Bid exceeds the other members' share of the pot")]
+    InvalidBid,
+
+    #[msg("Slot hashes sysvar has no entries")]
+    SlotHashUnavailable,
+
+    #[msg("Payout order has not been revealed yet")]
+    PayoutOrderNotRevealed,
+
+    #[msg("Payout order has already been revealed")]
+    PayoutOrderAlreadyRevealed,
+
+    #[msg("Reveal slot has not been reached yet")]
+    RevealNotReady,
+
+    #[msg("Cannot swap positions with yourself")]
+    CannotSwapWithSelf,
+
+    #[msg("Position has already been paid out")]
+    PositionAlreadyPaid,
+
+    #[msg("Positions changed since the swap was proposed")]
+    SwapPositionsChanged,
+
+    #[msg("Only the proposed counterparty can accept this swap")]
+    NotSwapCounterparty,
+
+    #[msg("Bidding for this payout round has closed")]
+    BiddingClosed,
+
+    #[msg("Late penalty settings are invalid")]
+    InvalidLatePolicy,
+
+    #[msg("Member has no contributions past their grace period")]
+    NotInDefault,
+
+    #[msg("Member's deposit already covers what they owe")]
+    AlreadySlashed,
+
+    #[msg("Member has defaulted and cannot receive a payout")]
+    MemberDefaulted,
+
+    #[msg("Member has not defaulted on enough rounds to be ejected")]
+    NotEjectable,
+
+    #[msg("Cannot vote to eject yourself")]
+    CannotEjectSelf,
+
+    #[msg("Another member is already being voted on")]
+    EjectionInProgress,
+
+    #[msg("Already voted to eject this member")]
+    AlreadyVotedToEject,
+
+    #[msg("Member has been ejected from the group")]
+    MemberEjected,
+
+    #[msg("Position is not vacant")]
+    PositionNotVacant,
+
+    #[msg("The creator cannot leave, cancel the group instead")]
+    CreatorCannotLeave,
+
+    #[msg("Join deadline must be in the future")]
+    InvalidJoinDeadline,
+
+    #[msg("Join deadline has passed")]
+    JoinDeadlinePassed,
+
+    #[msg("Join deadline has not passed yet")]
+    JoinDeadlineNotReached,
+
+    #[msg("Already voted to start early")]
+    AlreadyVotedToStart,
+
+    #[msg("Group cannot move to that status")]
+    InvalidStatusTransition,
+
+    #[msg("Group is paused")]
+    GroupPaused,
+
+    #[msg("The current cycle has not completed")]
+    CycleNotCompleted,
+
+    #[msg("Already opted into the next cycle")]
+    AlreadyOptedIn,
+
+    #[msg("Member has opted into the next cycle and keeps their deposit")]
+    OptedIntoNextCycle,
+
+    #[msg("Every member must opt in or withdraw their deposit before the next cycle")]
+    NextCycleNotSettled,
+
+    #[msg("Must prepay at least one round")]
+    InvalidPrepayment,
+
+    #[msg("Cannot prepay past the end of the cycle")]
+    PrepaymentExceedsCycle,
+
+    #[msg("Group would have more contribution rounds in a cycle than can be tracked")]
+    TooManyContributionRounds,
+
+    #[msg("Not opted into the next cycle")]
+    NotOptedIn,
+
+    #[msg("A vacant position can only be skipped once the next payout falls due")]
+    VacancyNotYetSkippable,
+
+    #[msg("Security deposit is too large to express in the mint's decimals")]
+    DepositOverflow,
+}
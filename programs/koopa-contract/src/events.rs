@@ -1,13 +1,18 @@
+use crate::state::*;
 use anchor_lang::prelude::*;
 
 #[event]
 pub struct AjoGroupCreatedEvent {
     pub group_name: String,
+    pub token_mint: Pubkey,
     pub security_deposit: u64,
     pub contribution_amount: u64,
     pub num_participants: u8,
     pub contribution_interval: u16,
     pub payout_interval: u16,
+    pub payout_order: PayoutOrder,
+    pub late_policy: LatePolicy,
+    pub join_deadline: Option<i64>,
 }
 
 #[event]
@@ -17,25 +22,303 @@ pub struct ParticipantJoinedEvent {
     pub join_timestamp: i64,
 }
 
+#[event]
+pub struct GroupStartedEarlyEvent {
+    pub group_name: String,
+    pub num_participants: u8,
+    pub planned_participants: u8,
+}
+
+#[event]
+pub struct ParticipantLeftEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub refund_amount: u64,
+}
+
 #[event]
 pub struct ContributionMadeEvent {
     pub group_name: String,
     pub contributor: Pubkey,
     pub contribution_amount: u64,
+    pub fee_amount: u64,
     pub current_round: u8,
 }
 
+#[event]
+pub struct ContributionPrepaidEvent {
+    pub group_name: String,
+    pub contributor: Pubkey,
+    pub rounds_ahead: u8,
+    pub prepaid_balance: u64,
+}
+
 #[event]
 pub struct PayoutMadeEvent {
     pub group_name: String,
     pub recipient: Pubkey,
     pub payout_amount: u64,
+    pub fee_amount: u64,
+    pub discount: u64,
+    pub late_penalties: u64,
     pub payout_round: u8,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MemberTotals {
+    pub member: Pubkey,
+    pub contributed: u64,
+    pub received: u64,
+}
+
+#[event]
+pub struct GroupCompletedEvent {
+    pub group_name: String,
+    pub total_payouts: u8,
+    pub members: Vec<MemberTotals>,
+}
+
 #[event]
 pub struct AjoGroupClosedEvent {
     pub group_name: String,
     pub total_votes: u8,
     pub group_size: u8,
 }
+
+#[event]
+pub struct AjoGroupCancelledEvent {
+    pub group_name: String,
+    pub group_size: u8,
+}
+
+#[event]
+pub struct GroupStatusChangedEvent {
+    pub group_name: String,
+    pub from: GroupStatus,
+    pub to: GroupStatus,
+}
+
+#[event]
+pub struct AjoGroupExpiredEvent {
+    pub group_name: String,
+    pub group_size: u8,
+    pub join_deadline: i64,
+}
+
+#[event]
+pub struct SecurityDepositWithdrawnEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub security_deposit: u64,
+    pub unspent_contributions: u64,
+    pub credit: u64,
+    pub penalty_share: u64,
+    pub refund_amount: u64,
+}
+
+#[event]
+pub struct GlobalConfigUpdatedEvent {
+    pub admin: Pubkey,
+    pub fee_percentage: u8,
+    pub fee_mode: FeeMode,
+    pub creator_security_deposit: u64,
+    pub joiner_security_deposit: u64,
+    pub limits: GroupLimits,
+}
+
+#[event]
+pub struct AdminProposedEvent {
+    pub current_admin: Pubkey,
+    pub proposed_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferredEvent {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct TreasuryMultisigUpdatedEvent {
+    pub admin: Pubkey,
+    pub treasury_multisig: Option<Pubkey>,
+}
+
+#[event]
+pub struct TreasuryWithdrawnEvent {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub total_withdrawn: u64,
+    pub remaining_revenue: u64,
+}
+
+#[event]
+pub struct ProtocolPauseUpdatedEvent {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowedMintUpdatedEvent {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct GlobalStateMigratedEvent {
+    pub admin: Pubkey,
+    pub total_groups: u64,
+}
+
+#[event]
+pub struct GroupVaultMigratedEvent {
+    pub group_name: String,
+    pub legacy_vault: Pubkey,
+    pub group_vault: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PayoutOrderSetEvent {
+    pub group_name: String,
+    pub fixed_order: Vec<Pubkey>,
+}
+
+#[event]
+pub struct PayoutBidPlacedEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub discount: u64,
+}
+
+#[event]
+pub struct PayoutScheduleEvent {
+    pub group_name: String,
+    pub payout_order: PayoutOrder,
+    pub payout_seed: Option<[u8; 32]>,
+    pub schedule: Vec<Pubkey>,
+}
+
+#[event]
+pub struct PayoutOrderCommittedEvent {
+    pub group_name: String,
+    pub commit_slot: u64,
+    pub reveal_slot: u64,
+}
+
+#[event]
+pub struct SwapProposedEvent {
+    pub group_name: String,
+    pub proposer: Pubkey,
+    pub counterparty: Pubkey,
+    pub proposer_claim_round: u8,
+    pub counterparty_claim_round: u8,
+    pub sweetener: u64,
+}
+
+#[event]
+pub struct SwapAcceptedEvent {
+    pub group_name: String,
+    pub proposer: Pubkey,
+    pub counterparty: Pubkey,
+    pub proposer_claim_round: u8,
+    pub counterparty_claim_round: u8,
+    pub sweetener: u64,
+}
+
+#[event]
+pub struct SwapCancelledEvent {
+    pub group_name: String,
+    pub proposer: Pubkey,
+    pub counterparty: Pubkey,
+}
+
+#[event]
+pub struct LateContributionEvent {
+    pub group_name: String,
+    pub contributor: Pubkey,
+    pub late_rounds: u8,
+    pub days_late: u64,
+    pub penalty_amount: u64,
+    pub destination: PenaltyDestination,
+}
+
+#[event]
+pub struct DefaulterSlashedEvent {
+    pub group_name: String,
+    pub defaulter: Pubkey,
+    pub slashed_by: Pubkey,
+    pub rounds_overdue: u8,
+    pub slashed_amount: u64,
+    pub remaining_deposit: u64,
+}
+
+#[event]
+pub struct DefaultClearedEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub restored_deposit: u64,
+}
+
+#[event]
+pub struct ParticipantEjectedEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub forfeited_deposit: u64,
+    pub total_votes: u8,
+}
+
+#[event]
+pub struct VacantPositionSkippedEvent {
+    pub group_name: String,
+    pub position: Pubkey,
+    pub payout_round: u8,
+    pub refunded_pot: u64,
+}
+
+#[event]
+pub struct ReplacementAdmittedEvent {
+    pub group_name: String,
+    pub replaced: Pubkey,
+    pub replacement: Pubkey,
+    pub claim_round: u8,
+    pub arrears: u64,
+    pub security_deposit: u64,
+}
+
+#[event]
+pub struct OptedIntoNextCycleEvent {
+    pub group_name: String,
+    pub member: Pubkey,
+    pub cycle: u8,
+}
+
+#[event]
+pub struct OptedOutOfNextCycleEvent {
+    pub group_name: String,
+    pub member: Pubkey,
+    pub cycle: u8,
+}
+
+#[event]
+pub struct NextCycleTermsSetEvent {
+    pub group_name: String,
+    pub cycle: u8,
+    pub contribution_amount: u64,
+    pub payout_order: PayoutOrder,
+    pub opt_ins_cleared: u8,
+}
+
+#[event]
+pub struct NextCycleStartedEvent {
+    pub group_name: String,
+    pub cycle: u8,
+    pub contribution_amount: u64,
+    pub payout_order: PayoutOrder,
+    pub members: u8,
+    pub open_slots: u8,
+}
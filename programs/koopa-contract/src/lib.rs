@@ -1,610 +1,3100 @@
-//lib.rs
-use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
-
-pub mod errors;
-pub mod events;
-pub mod state;
-pub mod utils;
-
-use errors::*;
-use events::*;
-use state::*;
-use utils::*;
-
-// This is your program's public key and it will update
-// automatically when you build the project.
-
-declare_id!("5upMRrwYFpvhkfmyUfb9Eun2EPWWu4XyBpkBLfUK2Tgm");
-
-#[program]
-mod koopa {
-    use super::*;
-
-    pub fn initialize(ctx: Context<Initialize>, fee_percentage: u8) -> Result<()> {
-        require!(fee_percentage <= 100, KooPaaError::InvalidFeePercentage);
-
-        let global_state = &mut ctx.accounts.global_state;
-
-        global_state.total_groups = 0;
-        global_state.total_revenue = 0;
-        global_state.active_groups = 0;
-        global_state.completed_groups = 0;
-        global_state.admin = ctx.accounts.admin.key();
-        global_state.fee_percentage = fee_percentage;
-
-        // Set fixed security deposit amounts in USDC with 6 decimals
-        global_state.creator_security_deposit = 5_000_000; // 5 USDC
-        global_state.joiner_security_deposit = 2_000_000; // 2 USDC
-
-        global_state.bumps = ctx.bumps.global_state;
-
-        Ok(())
-    }
-
-    pub fn create_ajo_group(
-        ctx: Context<CreateAjoGroup>,
-        name: String,
-        contribution_amount: u64,
-        contribution_interval: u16,
-        payout_interval: u16,
-        num_participants: u8,
-    ) -> Result<()> {
-        require!(
-            contribution_amount > 0,
-            KooPaaError::InvalidContributionAmount
-        );
-        require!(
-            contribution_interval > 0 && contribution_interval <= 90,
-            KooPaaError::InvalidInterval
-        );
-        require!(
-            payout_interval >= 7 && payout_interval <= 90,
-            KooPaaError::InvalidInterval
-        );
-        require!(
-            num_participants >= 3 && num_participants <= 20,
-            KooPaaError::InvalidParticipantCount
-        );
-        require!(name.len() <= 50, KooPaaError::NameTooLong);
-
-        // Use the fixed security deposit from global state
-        let security_deposit = ctx.accounts.global_state.creator_security_deposit;
-
-        // Transfer security deposit from creator to the vault
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.creator_token_account.to_account_info(),
-            to: ctx.accounts.group_token_vault.to_account_info(),
-            authority: ctx.accounts.creator.to_account_info(),
-        };
-
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
-            ),
-            security_deposit,
-        )?;
-
-        let group = &mut ctx.accounts.ajo_group;
-        let creator = &ctx.accounts.creator;
-        let global_state = &mut ctx.accounts.global_state;
-        let clock = Clock::get()?;
-
-        group.name = name.clone();
-        group.contribution_amount = contribution_amount;
-        group.contribution_interval = contribution_interval;
-        group.security_deposit = security_deposit;
-        group.payout_interval = payout_interval;
-        group.num_participants = num_participants - 1;
-
-        group.participants = vec![AjoParticipant {
-            pubkey: creator.key(),
-            claim_round: 0,
-            contribution_round: 0,
-            bump: ctx.bumps.group_token_vault,
-        }];
-        group.payout_round = 0;
-        group.start_timestamp = None;
-        group.close_votes = vec![];
-        group.is_closed = false;
-        group.bumps = ctx.bumps.ajo_group;
-
-        global_state.total_groups += 1;
-
-        emit!(AjoGroupCreatedEvent {
-            group_name: name.clone(),
-            security_deposit,
-            contribution_amount,
-            num_participants,
-            contribution_interval,
-            payout_interval,
-        });
-
-        emit!(ParticipantJoinedEvent {
-            group_name: name,
-            participant: creator.key(),
-            join_timestamp: clock.unix_timestamp,
-        });
-
-        Ok(())
-    }
-
-    pub fn join_ajo_group(ctx: Context<JoinAjoGroup>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let global_state = &mut ctx.accounts.global_state;
-        let participant = &ctx.accounts.participant;
-        let clock = Clock::get()?;
-
-        // Use the joiner security deposit from global state
-        let security_deposit = global_state.joiner_security_deposit;
-
-        // Transfer security deposit from participant to the vault
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.participant_token_account.to_account_info(),
-            to: ctx.accounts.group_token_vault.to_account_info(),
-            authority: participant.to_account_info(),
-        };
-
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
-            ),
-            security_deposit,
-        )?;
-
-        require!(
-            group.start_timestamp.is_none(),
-            KooPaaError::GroupAlreadyStarted
-        );
-
-        let already_joined = group
-            .participants
-            .iter()
-            .any(|p| p.pubkey == participant.key());
-
-        require!(!already_joined, KooPaaError::AlreadyJoined);
-
-        group.participants.push(AjoParticipant {
-            pubkey: participant.key(),
-            claim_round: 0,
-            contribution_round: 0,
-            bump: ctx.bumps.group_token_vault,
-        });
-
-        if group.participants.len() == group.num_participants as usize {
-            group.start_timestamp = Some(clock.unix_timestamp);
-            global_state.active_groups += 1;
-        }
-
-        emit!(ParticipantJoinedEvent {
-            group_name: group.name.clone(),
-            participant: participant.key(),
-            join_timestamp: clock.unix_timestamp,
-        });
-
-        Ok(())
-    }
-
-    pub fn contribute(ctx: Context<Contribute>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let contributor = &ctx.accounts.contributor;
-        let clock = Clock::get()?;
-
-        require!(
-            group.start_timestamp.is_some(),
-            KooPaaError::GroupNotStarted
-        );
-
-        // Get all values we need before the mutable borrow
-        let start_timestamp = group.start_timestamp.unwrap();
-        let contribution_interval = group.contribution_interval;
-        let contribution_amount = group.contribution_amount;
-
-        // Now find the participant and create a mutable reference
-        let participant = group
-            .participants
-            .iter_mut()
-            .find(|p| p.pubkey == contributor.key())
-            .ok_or(KooPaaError::NotParticipant)?;
-
-        let time_since_start = clock.unix_timestamp - start_timestamp;
-        let current_round = (time_since_start / contribution_interval as i64) as u8;
-
-        let last_paid_round = participant.contribution_round;
-        require!(
-            last_paid_round < current_round,
-            KooPaaError::AlreadyContributed
-        );
-
-        let rounds_missed = current_round - last_paid_round;
-        let transfer_amount = contribution_amount * rounds_missed as u64;
-
-        // Transfer tokens from contributor to the group vault
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.contributor_token_account.to_account_info(),
-            to: ctx.accounts.group_token_vault.to_account_info(),
-            authority: contributor.to_account_info(),
-        };
-
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
-            ),
-            transfer_amount,
-        )?;
-
-        participant.contribution_round = current_round;
-
-        emit!(ContributionMadeEvent {
-            group_name: group.name.clone(),
-            contributor: contributor.key(),
-            contribution_amount: transfer_amount,
-            current_round,
-        });
-
-        Ok(())
-    }
-
-    pub fn claim_round(ctx: Context<ClaimRound>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let recipient = &ctx.accounts.recipient;
-        let clock = Clock::get()?;
-
-        // Check if the group has started
-        require!(
-            group.start_timestamp.is_some(),
-            KooPaaError::GroupNotStarted
-        );
-
-        // Check if the group is closed
-        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
-
-        // Find the recipient in the group
-        let recipient_index = group
-            .participants
-            .iter()
-            .position(|p| p.pubkey == recipient.key())
-            .ok_or(KooPaaError::NotParticipant)?;
-
-        // Calculate current round based on time elapsed
-        let time_since_start = clock.unix_timestamp - group.start_timestamp.unwrap();
-        let payout_interval_secs = group.payout_interval as i64 * 86400; // Convert days to seconds
-        let current_round = (time_since_start / payout_interval_secs) as u8;
-
-        // Check if this is the recipient's turn
-        let expected_recipient_index = (group.payout_round as usize) % group.participants.len();
-        require!(
-            recipient_index == expected_recipient_index,
-            KooPaaError::NotCurrentRecipient
-        );
-
-        // Check if all participants have contributed for this round
-        let all_contributed = group
-            .participants
-            .iter()
-            .all(|p| p.contribution_round >= current_round);
-
-        require!(all_contributed, KooPaaError::NotAllContributed);
-
-        // Calculate the total amount to be claimed
-        let claim_amount = group.contribution_amount * (group.participants.len() as u64);
-
-        // Transfer the funds (handled in a separate payout instruction)
-        // This function just checks eligibility
-
-        Ok(())
-    }
-
-    pub fn payout(ctx: Context<Payout>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let clock = Clock::get()?;
-
-        let start_timestamp = group.start_timestamp.ok_or(KooPaaError::GroupNotStarted)?;
-        let time_since_start = clock.unix_timestamp - start_timestamp;
-
-        let payout_interval_secs = group.payout_interval as i64 * 86400; // Convert days to seconds
-        let expected_round = (time_since_start / payout_interval_secs) as u8;
-
-        require!(
-            group.payout_round < expected_round,
-            KooPaaError::PayoutNotYetDue
-        );
-
-        let num_participants = group.participants.len() as u8;
-        let recipient_index = (group.payout_round as usize) % (num_participants as usize);
-        let recipient_pubkey = group.participants[recipient_index].pubkey;
-
-        // Verify the recipient is the correct one
-        require!(
-            recipient_pubkey == ctx.accounts.recipient.key(),
-            KooPaaError::NotCurrentRecipient
-        );
-
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.group_token_vault.to_account_info(),
-            to: ctx.accounts.recipient_token_account.to_account_info(),
-            authority: ctx.accounts.group_signer.to_account_info(),
-        };
-
-        // Each participant contributes the contribution_amount
-        let payout_amount = group.contribution_amount * (num_participants as u64);
-
-        // Get the correct seeds for the vault PDA
-        let group_name = group.name.clone();
-        let signer_seeds = &[
-            b"group-vault",
-            group_name.as_bytes(),
-            &[ctx.bumps.group_signer],
-        ];
-
-        transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
-                &[signer_seeds],
-            ),
-            payout_amount,
-        )?;
-
-        group.payout_round += 1;
-
-        emit!(PayoutMadeEvent {
-            group_name: group.name.clone(),
-            recipient: recipient_pubkey,
-            payout_amount,
-            payout_round: group.payout_round,
-        });
-
-        Ok(())
-    }
-
-    pub fn close_ajo_group(ctx: Context<CloseAjoGroup>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let participant = &ctx.accounts.participant;
-        let global_state = &mut ctx.accounts.global_state;
-
-        if group.is_closed {
-            return err!(KooPaaError::GroupAlreadyClosed);
-        }
-
-        // Check if the caller is a participant
-        let is_participant = group
-            .participants
-            .iter()
-            .any(|p| p.pubkey == participant.key());
-
-        require!(is_participant, KooPaaError::NotParticipant);
-
-        // Check if they've already voted
-        let already_voted = group.close_votes.contains(&participant.key());
-        if already_voted {
-            return err!(KooPaaError::AlreadyVotedToClose);
-        }
-
-        // Add their vote
-        group.close_votes.push(participant.key());
-
-        let total_participants = group.participants.len();
-        let total_votes = group.close_votes.len();
-
-        // If majority votes to close
-        if total_votes * 2 > total_participants {
-            // Refund security deposits if group has started
-            if group.start_timestamp.is_some() {
-                global_state.active_groups -= 1;
-            }
-
-            // Mark group as permanently inactive
-            group.is_closed = true;
-
-            emit!(AjoGroupClosedEvent {
-                group_name: group.name.clone(),
-                total_votes: total_votes as u8,
-                group_size: total_participants as u8,
-            });
-        }
-
-        Ok(())
-    }
-}
-
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = admin,
-        space = GlobalState::SIZE,
-        seeds = [b"global-state"],
-        bump
-    )]
-    pub global_state: Account<'info, GlobalState>,
-
-    #[account(mut)]
-    pub admin: Signer<'info>,
-
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-#[instruction(
-    name: String,
-    contribution_amount: u64,
-    contribution_interval: u16,
-    payout_interval: u16,
-    num_participants: u8
-)]
-pub struct CreateAjoGroup<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = AjoGroup::calculate_size(&name),
-        seeds = [b"ajo-group", name.as_bytes()],
-        bump
-    )]
-    pub ajo_group: Account<'info, AjoGroup>,
-
-    #[account(mut)]
-    pub creator: Signer<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"global-state"],
-        bump = global_state.bumps
-    )]
-    pub global_state: Account<'info, GlobalState>,
-
-    pub token_mint: Account<'info, Mint>,
-
-    #[account(
-        mut,
-        constraint = creator_token_account.owner == creator.key(),
-        constraint = creator_token_account.mint == token_mint.key()
-    )]
-    pub creator_token_account: Account<'info, TokenAccount>,
-
-    #[account(
-        init,
-        payer = creator,
-        seeds = [b"group-vault", name.as_bytes()],
-        bump,
-        token::mint = token_mint,
-        token::authority = creator
-    )]
-    pub group_token_vault: Account<'info, TokenAccount>,
-
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
-
-#[derive(Accounts)]
-pub struct JoinAjoGroup<'info> {
-    #[account(mut)]
-    pub ajo_group: Account<'info, AjoGroup>,
-
-    #[account(mut)]
-    pub participant: Signer<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"global-state"],
-        bump = global_state.bumps
-    )]
-    pub global_state: Account<'info, GlobalState>,
-
-    pub token_mint: Account<'info, Mint>,
-
-    #[account(
-        mut,
-        constraint = participant_token_account.owner == participant.key(),
-        constraint = participant_token_account.mint == token_mint.key()
-    )]
-    pub participant_token_account: Account<'info, TokenAccount>,
-
-    #[account(
-        mut,
-        seeds = [b"group-vault", ajo_group.key().as_ref()],
-        bump
-    )]
-    pub group_token_vault: Account<'info, TokenAccount>,
-
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct Contribute<'info> {
-    #[account(mut)]
-    pub ajo_group: Account<'info, AjoGroup>,
-
-    pub contributor: Signer<'info>,
-
-    #[account(
-        mut,
-        constraint = contributor_token_account.owner == contributor.key(),
-        constraint = contributor_token_account.mint == token_mint.key(),
-    )]
-    pub contributor_token_account: Account<'info, TokenAccount>,
-
-    #[account(
-        mut,
-        seeds = [b"group-vault", ajo_group.key().as_ref()],
-        bump
-    )]
-    pub group_token_vault: Account<'info, TokenAccount>,
-
-    #[account(
-        mut,
-        seeds = [b"global-state"],
-        bump = global_state.bumps
-    )]
-    pub global_state: Account<'info, GlobalState>,
-
-    pub token_mint: Account<'info, Mint>,
-
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct ClaimRound<'info> {
-    #[account(mut)]
-    pub ajo_group: Account<'info, AjoGroup>,
-
-    pub recipient: Signer<'info>,
-
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct Payout<'info> {
-    #[account(mut)]
-    pub ajo_group: Account<'info, AjoGroup>,
-
-    #[account(
-        seeds = [b"group-vault", ajo_group.name.as_bytes()],
-        bump,
-    )]
-    /// CHECK: This is the PDA that signs for the vault
-    pub group_signer: UncheckedAccount<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"group-vault", ajo_group.name.as_bytes()],
-        bump,
-    )]
-    pub group_token_vault: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub recipient: Signer<'info>,
-
-    #[account(
-        mut,
-        constraint = recipient_token_account.owner == recipient.key(),
-        constraint = recipient_token_account.mint == token_mint.key()
-    )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
-
-    pub token_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
-}
-
-#[derive(Accounts)]
-pub struct CloseAjoGroup<'info> {
-    #[account(mut)]
-    pub ajo_group: Account<'info, AjoGroup>,
-
-    pub participant: Signer<'info>,
-
-    #[account(
-        mut,
-        seeds = [b"global-state"],
-        bump = global_state.bumps
-    )]
-    pub global_state: Account<'info, GlobalState>,
-
-    pub system_program: Program<'info, System>,
-}
+//lib.rs
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+
+pub mod errors;
+pub mod events;
+pub mod schedule;
+pub mod state;
+pub mod utils;
+
+use errors::*;
+use events::*;
+use schedule::*;
+use state::*;
+use utils::*;
+
+// This is your program's public key and it will update
+// automatically when you build the project.
+
+declare_id!("5upMRrwYFpvhkfmyUfb9Eun2EPWWu4XyBpkBLfUK2Tgm");
+
+// Shared by pause/unpause; deposit withdrawals and group closing stay available while paused
+fn set_paused(ctx: Context<AdminUpdate>, paused: bool) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    let clock = Clock::get()?;
+
+    // Running groups move their schedules back by the time spent paused
+    if paused && global_state.paused_at.is_none() {
+        global_state.paused_at = Some(clock.unix_timestamp);
+    } else if let Some(paused_at) = global_state.paused_at.filter(|_| !paused) {
+        global_state.paused_seconds += clock.unix_timestamp - paused_at;
+        global_state.paused_at = None;
+    }
+    global_state.paused = paused;
+
+    emit!(ProtocolPauseUpdatedEvent {
+        admin: global_state.admin,
+        paused,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Every status change goes through here so illegal transitions are rejected in one place
+fn transition(group: &mut AjoGroup, next: GroupStatus) -> Result<()> {
+    require!(
+        group.status.can_transition_to(next),
+        KooPaaError::InvalidStatusTransition
+    );
+
+    // Slashed deposits nobody won back are shared out with the refunds once the group
+    // stops running
+    if matches!(next, GroupStatus::Settling | GroupStatus::Completed) {
+        for participant in group.participants.iter_mut() {
+            group.penalty_pool += participant.slashed - participant.covered;
+            participant.slashed = 0;
+            participant.covered = 0;
+        }
+    }
+
+    emit!(GroupStatusChangedEvent {
+        group_name: group.name.clone(),
+        from: group.status,
+        to: next,
+    });
+    group.status = next;
+
+    Ok(())
+}
+
+// The cycle is over once every position has been paid out or skipped
+fn complete_if_paid_out(group: &mut AjoGroup, global_state: &mut GlobalState) -> Result<()> {
+    if (group.payout_round as usize) < group.participants.len() {
+        return Ok(());
+    }
+
+    transition(group, GroupStatus::Completed)?;
+    global_state.active_groups -= 1;
+    global_state.completed_groups += 1;
+
+    emit!(GroupCompletedEvent {
+        group_name: group.name.clone(),
+        total_payouts: group.payout_round,
+        members: group
+            .participants
+            .iter()
+            .map(|p| MemberTotals {
+                member: p.pubkey,
+                contributed: p.total_contributed,
+                received: p.total_received,
+            })
+            .collect(),
+    });
+
+    Ok(())
+}
+
+// Admin halt of a single group; members can still vote to close it while paused.
+// Resuming moves the schedule back by the length of the pause
+fn set_group_paused(ctx: Context<GroupAdminUpdate>, paused: bool) -> Result<()> {
+    let group = &mut ctx.accounts.ajo_group;
+    let now = Clock::get()?.unix_timestamp;
+
+    apply_protocol_pause(group, &ctx.accounts.global_state, now);
+
+    if paused {
+        transition(group, GroupStatus::Paused)?;
+        group.paused_at = Some(now);
+    } else {
+        transition(group, GroupStatus::Active)?;
+        if let (Some(start), Some(paused_at)) = (group.start_timestamp, group.paused_at.take()) {
+            group.start_timestamp = Some(start + now - paused_at);
+        }
+    }
+
+    Ok(())
+}
+
+// Starts a full group and freezes its payout order, or commits to a future slot hash
+// when the order is revealed later through reveal_payout_order
+fn start_group(
+    group: &mut AjoGroup,
+    global_state: &mut GlobalState,
+    slot_hashes: &AccountInfo,
+    clock: &Clock,
+) -> Result<()> {
+    transition(group, GroupStatus::Active)?;
+    group.start_timestamp = Some(clock.unix_timestamp);
+    group.paused_at = None;
+    group.protocol_paused_seconds = global_state.paused_seconds_at(clock.unix_timestamp);
+    global_state.active_groups += 1;
+
+    if group.payout_order == PayoutOrder::VerifiableRandom {
+        commit_payout_order(group, clock.slot);
+        return Ok(());
+    }
+
+    let seed = latest_slot_hash(slot_hashes)?;
+    assign_claim_rounds(group, &seed);
+    if group.payout_order == PayoutOrder::Random {
+        group.payout_seed = Some(seed);
+    }
+
+    emit!(PayoutScheduleEvent {
+        group_name: group.name.clone(),
+        payout_order: group.payout_order,
+        payout_seed: group.payout_seed,
+        schedule: payout_schedule(group),
+    });
+
+    Ok(())
+}
+
+// Nobody can know the hash of a slot that has not been produced yet
+fn commit_payout_order(group: &mut AjoGroup, slot: u64) {
+    group.order_commit_slot = slot;
+
+    emit!(PayoutOrderCommittedEvent {
+        group_name: group.name.clone(),
+        commit_slot: slot,
+        reveal_slot: slot + AjoGroup::REVEAL_DELAY_SLOTS,
+    });
+}
+
+// Grows a program account rewritten in a larger layout, topping up its rent from `payer`
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    new_size: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(new_size)
+        .saturating_sub(account.lamports());
+    if rent_due > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    account.realloc(new_size, true)?;
+
+    Ok(())
+}
+
+// Pays out whatever a swap escrow holds and closes it, returning its rent to the proposer
+fn release_swap_escrow<'info>(
+    proposal: &Account<'info, SwapProposal>,
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    proposer: &AccountInfo<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    // The proposal PDA is the escrow authority
+    let signer_seeds: &[&[u8]] = &[
+        b"swap",
+        proposal.ajo_group.as_ref(),
+        proposal.proposer.as_ref(),
+        &[proposal.bumps],
+    ];
+
+    if escrow.amount > 0 {
+        let transfer_accounts = TransferChecked {
+            from: escrow.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: destination.to_account_info(),
+            authority: proposal.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                transfer_accounts,
+                &[signer_seeds],
+            ),
+            escrow.amount,
+            token_mint.decimals,
+        )?;
+    }
+
+    let close_accounts = CloseAccount {
+        account: escrow.to_account_info(),
+        destination: proposer.clone(),
+        authority: proposal.to_account_info(),
+    };
+
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        close_accounts,
+        &[signer_seeds],
+    ))
+}
+
+// Shared by contribute/contribute_ahead; pays every round owed up to the current one,
+// plus `rounds_ahead` rounds that have not opened yet
+fn contribute_rounds(ctx: Context<Contribute>, rounds_ahead: u8) -> Result<()> {
+    let group = &mut ctx.accounts.ajo_group;
+    let global_state = &ctx.accounts.global_state;
+    let contributor = &ctx.accounts.contributor;
+    let clock = Clock::get()?;
+
+    require_active(group)?;
+    apply_protocol_pause(group, global_state, clock.unix_timestamp);
+
+    let contribution_amount = group.contribution_amount;
+
+    // Find the participant; they are updated by index once the transfers are done
+    let participant_index = group
+        .participants
+        .iter()
+        .position(|p| p.pubkey == contributor.key())
+        .ok_or(KooPaaError::NotParticipant)?;
+
+    let current_round = current_contribution_round(group, clock.unix_timestamp);
+
+    require!(
+        !group.participants[participant_index].ejected,
+        KooPaaError::MemberEjected
+    );
+
+    // Rounds already prepaid are built on rather than paid again
+    let last_paid_round = group.participants[participant_index].contribution_round;
+    let target_round = last_paid_round
+        .max(current_round)
+        .checked_add(rounds_ahead)
+        .ok_or(KooPaaError::PrepaymentExceedsCycle)?;
+    require!(
+        last_paid_round < target_round,
+        KooPaaError::AlreadyContributed
+    );
+    require!(
+        target_round <= total_contribution_rounds(group),
+        KooPaaError::PrepaymentExceedsCycle
+    );
+
+    let rounds_missed = target_round - last_paid_round;
+    let contribution_total = contribution_amount * rounds_missed as u64;
+
+    // Every round paid after its grace period carries a late penalty
+    let mut penalty_amount = 0;
+    let mut late_rounds = 0;
+    let mut days_late = 0;
+    for round in last_paid_round + 1..=target_round {
+        let (penalty, days) = calculate_late_penalty(group, round, clock.unix_timestamp);
+        if days > 0 {
+            penalty_amount += penalty;
+            late_rounds += 1;
+            days_late = days_late.max(days);
+        }
+    }
+    let transfer_amount = contribution_total + penalty_amount;
+
+    // Transfer tokens from contributor to the group vault, grossed up so the
+    // full contribution still lands if the mint charges a transfer fee
+    let transfer_accounts = TransferChecked {
+        from: ctx.accounts.contributor_token_account.to_account_info(),
+        mint: ctx.accounts.token_mint.to_account_info(),
+        to: ctx.accounts.group_token_vault.to_account_info(),
+        authority: contributor.to_account_info(),
+    };
+
+    let gross_amount =
+        gross_up_for_transfer_fee(&ctx.accounts.token_mint.to_account_info(), transfer_amount)?;
+    require!(
+        ctx.accounts.contributor_token_account.amount >= gross_amount,
+        KooPaaError::InsufficientFunds
+    );
+
+    let vault_balance_before = ctx.accounts.group_token_vault.amount;
+
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_accounts,
+        ),
+        gross_amount,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    // Only whole rounds covered by what actually landed in the vault are credited,
+    // anything beyond the contributions is the late penalty
+    ctx.accounts.group_token_vault.reload()?;
+    let received_amount = ctx.accounts.group_token_vault.amount - vault_balance_before;
+    let penalty_received = received_amount.saturating_sub(contribution_total);
+    let rounds_credited = ((received_amount - penalty_received) / contribution_amount)
+        .min(rounds_missed as u64) as u8;
+    require!(rounds_credited > 0, KooPaaError::TransferFeeShortfall);
+
+    // Protocol fee is charged on top of the contribution so the pot stays whole
+    let fee_amount = if global_state.fee_mode == FeeMode::OnContribution {
+        calculate_fee(contribution_total, global_state.fee_percentage)
+    } else {
+        0
+    };
+
+    if fee_amount > 0 {
+        let fee_accounts = TransferChecked {
+            from: ctx.accounts.contributor_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: contributor.to_account_info(),
+        };
+
+        let treasury_balance_before = ctx.accounts.treasury_token_account.amount;
+
+        transfer_checked(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), fee_accounts),
+            gross_up_for_transfer_fee(&ctx.accounts.token_mint.to_account_info(), fee_amount)?,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        ctx.accounts.treasury_token_account.reload()?;
+        ctx.accounts.treasury_state.total_revenue +=
+            ctx.accounts.treasury_token_account.amount - treasury_balance_before;
+    }
+
+    settle_late_rounds(
+        group,
+        participant_index,
+        last_paid_round + 1,
+        last_paid_round + rounds_credited,
+    );
+
+    let group_name = group.name.clone();
+    let participant = &mut group.participants[participant_index];
+    participant.contribution_round = last_paid_round + rounds_credited;
+    participant.total_contributed += received_amount - penalty_received;
+
+    // Catching up in full means the slashed deposit is no longer covering the pot
+    if participant.defaulted && participant.contribution_round >= current_round {
+        let restored_deposit = participant.slashed;
+        participant.security_deposit += restored_deposit;
+        participant.slashed = 0;
+        participant.defaulted = false;
+
+        emit!(DefaultClearedEvent {
+            group_name,
+            participant: contributor.key(),
+            restored_deposit,
+        });
+    }
+
+    if late_rounds > 0 {
+        match group.late_policy.destination {
+            PenaltyDestination::CurrentRecipient => group.round_bonus += penalty_received,
+            PenaltyDestination::Vault => group.penalty_pool += penalty_received,
+        }
+
+        emit!(LateContributionEvent {
+            group_name: group.name.clone(),
+            contributor: contributor.key(),
+            late_rounds,
+            days_late,
+            penalty_amount: penalty_received,
+            destination: group.late_policy.destination,
+        });
+    }
+
+    emit!(ContributionMadeEvent {
+        group_name: group.name.clone(),
+        contributor: contributor.key(),
+        contribution_amount: received_amount - penalty_received,
+        fee_amount,
+        current_round,
+    });
+
+    if rounds_ahead > 0 {
+        let participant = &group.participants[participant_index];
+        emit!(ContributionPrepaidEvent {
+            group_name: group.name.clone(),
+            contributor: contributor.key(),
+            rounds_ahead,
+            prepaid_balance: prepaid_balance(group, participant, clock.unix_timestamp),
+        });
+    }
+
+    Ok(())
+}
+
+#[program]
+mod koopa {
+    use super::*;
+
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        fee_percentage: u8,
+        fee_mode: FeeMode,
+    ) -> Result<()> {
+        require!(fee_percentage <= 100, KooPaaError::InvalidFeePercentage);
+
+        let global_state = &mut ctx.accounts.global_state;
+
+        global_state.total_groups = 0;
+        global_state.active_groups = 0;
+        global_state.completed_groups = 0;
+        global_state.expired_groups = 0;
+        global_state.admin = ctx.accounts.admin.key();
+        global_state.fee_percentage = fee_percentage;
+        global_state.fee_mode = fee_mode;
+
+        // Set fixed security deposit amounts in USDC with 6 decimals
+        global_state.creator_security_deposit = 5_000_000; // 5 USDC
+        global_state.joiner_security_deposit = 2_000_000; // 2 USDC
+
+        global_state.limits = GroupLimits::DEFAULT;
+        global_state.pending_admin = None;
+        global_state.treasury_multisig = None;
+        global_state.paused = false;
+        global_state.paused_at = None;
+        global_state.paused_seconds = 0;
+        global_state.bumps = ctx.bumps.global_state;
+
+        Ok(())
+    }
+
+    pub fn update_global_config(
+        ctx: Context<AdminUpdate>,
+        params: GlobalConfigParams,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        if let Some(fee_percentage) = params.fee_percentage {
+            require!(fee_percentage <= 100, KooPaaError::InvalidFeePercentage);
+            global_state.fee_percentage = fee_percentage;
+        }
+
+        if let Some(fee_mode) = params.fee_mode {
+            global_state.fee_mode = fee_mode;
+        }
+
+        if let Some(deposit) = params.creator_security_deposit {
+            require!(deposit > 0, KooPaaError::InvalidSecurityDeposit);
+            global_state.creator_security_deposit = deposit;
+        }
+
+        if let Some(deposit) = params.joiner_security_deposit {
+            require!(deposit > 0, KooPaaError::InvalidSecurityDeposit);
+            global_state.joiner_security_deposit = deposit;
+        }
+
+        if let Some(limits) = params.limits {
+            require!(limits.is_valid(), KooPaaError::InvalidGroupLimits);
+            global_state.limits = limits;
+        }
+
+        emit!(GlobalConfigUpdatedEvent {
+            admin: global_state.admin,
+            fee_percentage: global_state.fee_percentage,
+            fee_mode: global_state.fee_mode,
+            creator_security_deposit: global_state.creator_security_deposit,
+            joiner_security_deposit: global_state.joiner_security_deposit,
+            limits: global_state.limits,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_admin(ctx: Context<AdminUpdate>, new_admin: Pubkey) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        global_state.pending_admin = Some(new_admin);
+
+        emit!(AdminProposedEvent {
+            current_admin: global_state.admin,
+            proposed_admin: new_admin,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        let previous_admin = global_state.admin;
+
+        global_state.admin = ctx.accounts.new_admin.key();
+        global_state.pending_admin = None;
+
+        emit!(AdminTransferredEvent {
+            previous_admin,
+            new_admin: global_state.admin,
+        });
+
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<AdminUpdate>) -> Result<()> {
+        set_paused(ctx, true)
+    }
+
+    pub fn unpause(ctx: Context<AdminUpdate>) -> Result<()> {
+        set_paused(ctx, false)
+    }
+
+    pub fn pause_group(ctx: Context<GroupAdminUpdate>) -> Result<()> {
+        set_group_paused(ctx, true)
+    }
+
+    pub fn resume_group(ctx: Context<GroupAdminUpdate>) -> Result<()> {
+        set_group_paused(ctx, false)
+    }
+
+    pub fn add_allowed_mint(ctx: Context<AddAllowedMint>) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        let decimals = ctx.accounts.token_mint.decimals;
+
+        // Groups in this mint have to be able to take both security deposits
+        scale_to_decimals(global_state.creator_security_deposit, decimals)?;
+        scale_to_decimals(global_state.joiner_security_deposit, decimals)?;
+
+        let allowed_mint = &mut ctx.accounts.allowed_mint;
+        allowed_mint.mint = ctx.accounts.token_mint.key();
+        allowed_mint.bumps = ctx.bumps.allowed_mint;
+
+        emit!(AllowedMintUpdatedEvent {
+            admin: ctx.accounts.admin.key(),
+            mint: allowed_mint.mint,
+            allowed: true,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_allowed_mint(ctx: Context<RemoveAllowedMint>) -> Result<()> {
+        // Existing groups keep working; only new groups are blocked from this mint
+        emit!(AllowedMintUpdatedEvent {
+            admin: ctx.accounts.admin.key(),
+            mint: ctx.accounts.allowed_mint.mint,
+            allowed: false,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        // The treasury token account is created by the account constraints,
+        // owned by the global state PDA so only program logic can move fees
+        let treasury_state = &mut ctx.accounts.treasury_state;
+
+        treasury_state.mint = ctx.accounts.token_mint.key();
+        treasury_state.total_revenue = 0;
+        treasury_state.total_withdrawn = 0;
+        treasury_state.bumps = ctx.bumps.treasury_state;
+
+        Ok(())
+    }
+
+    pub fn set_treasury_multisig(
+        ctx: Context<AdminUpdate>,
+        treasury_multisig: Option<Pubkey>,
+    ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        global_state.treasury_multisig = treasury_multisig;
+
+        emit!(TreasuryMultisigUpdatedEvent {
+            admin: global_state.admin,
+            treasury_multisig,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        let treasury_state = &mut ctx.accounts.treasury_state;
+
+        // Each treasury is bounded by the fees collected in its own mint
+        require!(
+            amount <= treasury_state.available_revenue(),
+            KooPaaError::WithdrawalExceedsRevenue
+        );
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.destination_token_account.to_account_info(),
+            authority: global_state.to_account_info(),
+        };
+
+        let signer_seeds: &[&[u8]] = &[b"global-state", &[global_state.bumps]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                &[signer_seeds],
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        treasury_state.total_withdrawn += amount;
+
+        emit!(TreasuryWithdrawnEvent {
+            authority: ctx.accounts.authority.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            amount,
+            total_withdrawn: treasury_state.total_withdrawn,
+            remaining_revenue: treasury_state.available_revenue(),
+        });
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_ajo_group(
+        ctx: Context<CreateAjoGroup>,
+        name: String,
+        contribution_amount: u64,
+        contribution_interval: u16,
+        payout_interval: u16,
+        num_participants: u8,
+        payout_order: PayoutOrder,
+        late_policy: LatePolicy,
+        join_deadline: Option<i64>,
+    ) -> Result<()> {
+        require!(
+            contribution_amount > 0,
+            KooPaaError::InvalidContributionAmount
+        );
+
+        let limits = ctx.accounts.global_state.limits;
+        require!(
+            (limits.min_contribution_interval..=limits.max_contribution_interval)
+                .contains(&contribution_interval),
+            KooPaaError::InvalidInterval
+        );
+        require!(
+            (limits.min_payout_interval..=limits.max_payout_interval).contains(&payout_interval),
+            KooPaaError::InvalidInterval
+        );
+
+        // Every pot has to be funded by a whole number of contribution rounds
+        require!(
+            payout_interval.is_multiple_of(contribution_interval),
+            KooPaaError::InvalidInterval
+        );
+        require!(
+            (limits.min_participants..=limits.max_participants).contains(&num_participants),
+            KooPaaError::InvalidParticipantCount
+        );
+
+        // Contribution rounds are counted in a u8 over the whole cycle
+        let cycle_rounds =
+            num_participants as u32 * (payout_interval / contribution_interval) as u32;
+        require!(
+            cycle_rounds <= u8::MAX as u32,
+            KooPaaError::TooManyContributionRounds
+        );
+        require!(late_policy.is_valid(), KooPaaError::InvalidLatePolicy);
+
+        let clock = Clock::get()?;
+        require!(
+            join_deadline.is_none_or(|deadline| deadline > clock.unix_timestamp),
+            KooPaaError::InvalidJoinDeadline
+        );
+
+        // Scale the creator security deposit from global state to the group's mint
+        let security_deposit = scale_to_decimals(
+            ctx.accounts.global_state.creator_security_deposit,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Transfer security deposit from creator to the vault, grossed up so the
+        // full deposit still lands if the mint charges a transfer fee
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.creator_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.group_token_vault.to_account_info(),
+            authority: ctx.accounts.creator.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            gross_up_for_transfer_fee(
+                &ctx.accounts.token_mint.to_account_info(),
+                security_deposit,
+            )?,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Credit the deposit that actually landed in the vault
+        ctx.accounts.group_token_vault.reload()?;
+        let security_deposit = ctx.accounts.group_token_vault.amount;
+
+        let group = &mut ctx.accounts.ajo_group;
+        let creator = &ctx.accounts.creator;
+        let global_state = &mut ctx.accounts.global_state;
+
+        group.name = name.clone();
+        group.creator = creator.key();
+        group.token_mint = ctx.accounts.token_mint.key();
+        group.contribution_amount = contribution_amount;
+        group.contribution_interval = contribution_interval;
+        group.security_deposit = security_deposit;
+        group.payout_interval = payout_interval;
+        group.num_participants = num_participants;
+        group.payout_order = payout_order;
+        group.fixed_order = vec![];
+        group.order_commit_slot = 0;
+        group.payout_seed = None;
+        group.late_policy = late_policy;
+        group.round_bonus = 0;
+        group.penalty_pool = 0;
+        group.join_deadline = join_deadline;
+
+        group.participants = vec![AjoParticipant {
+            pubkey: creator.key(),
+            claim_round: 0,
+            contribution_round: 0,
+            bump: ctx.bumps.group_token_vault,
+            security_deposit,
+            refunded: false,
+            bid: 0,
+            credit: 0,
+            slashed: 0,
+            covered: 0,
+            defaulted: false,
+            ejected: false,
+            total_contributed: 0,
+            total_received: 0,
+        }];
+        group.payout_round = 0;
+        group.status = GroupStatus::Recruiting;
+        group.start_timestamp = None;
+        group.paused_at = None;
+        group.protocol_paused_seconds = 0;
+        group.start_votes = vec![];
+        group.close_votes = vec![];
+        group.eject_candidate = None;
+        group.eject_votes = vec![];
+        group.cycle = 1;
+        group.next_cycle_members = vec![];
+        group.next_cycle_terms = None;
+        group.bumps = ctx.bumps.ajo_group;
+
+        global_state.total_groups += 1;
+
+        emit!(AjoGroupCreatedEvent {
+            group_name: name.clone(),
+            token_mint: group.token_mint,
+            security_deposit,
+            contribution_amount,
+            num_participants,
+            contribution_interval,
+            payout_interval,
+            payout_order,
+            late_policy,
+            join_deadline,
+        });
+
+        emit!(ParticipantJoinedEvent {
+            group_name: name,
+            participant: creator.key(),
+            join_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn join_ajo_group(ctx: Context<JoinAjoGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &mut ctx.accounts.global_state;
+        let participant = &ctx.accounts.participant;
+        let clock = Clock::get()?;
+
+        // Scale the joiner security deposit from global state to the group's mint
+        let security_deposit = scale_to_decimals(
+            global_state.joiner_security_deposit,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Transfer security deposit from participant to the vault, grossed up so the
+        // full deposit still lands if the mint charges a transfer fee
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.participant_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.group_token_vault.to_account_info(),
+            authority: participant.to_account_info(),
+        };
+
+        let vault_balance_before = ctx.accounts.group_token_vault.amount;
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            gross_up_for_transfer_fee(
+                &ctx.accounts.token_mint.to_account_info(),
+                security_deposit,
+            )?,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Credit the deposit that actually landed in the vault
+        ctx.accounts.group_token_vault.reload()?;
+        let security_deposit = ctx.accounts.group_token_vault.amount - vault_balance_before;
+
+        require_recruiting(group)?;
+        require!(
+            group
+                .join_deadline
+                .is_none_or(|deadline| clock.unix_timestamp <= deadline),
+            KooPaaError::JoinDeadlinePassed
+        );
+
+        let already_joined = group
+            .participants
+            .iter()
+            .any(|p| p.pubkey == participant.key());
+
+        require!(!already_joined, KooPaaError::AlreadyJoined);
+
+        group.participants.push(AjoParticipant {
+            pubkey: participant.key(),
+            claim_round: 0,
+            contribution_round: 0,
+            bump: ctx.bumps.group_token_vault,
+            security_deposit,
+            refunded: false,
+            bid: 0,
+            credit: 0,
+            slashed: 0,
+            covered: 0,
+            defaulted: false,
+            ejected: false,
+            total_contributed: 0,
+            total_received: 0,
+        });
+
+        if group.participants.len() == group.num_participants as usize {
+            start_group(group, global_state, &ctx.accounts.slot_hashes, &clock)?;
+        }
+
+        emit!(ParticipantJoinedEvent {
+            group_name: group.name.clone(),
+            participant: participant.key(),
+            join_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn vote_start_early(ctx: Context<VoteStartEarly>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &mut ctx.accounts.global_state;
+        let participant = ctx.accounts.participant.key();
+        let clock = Clock::get()?;
+
+        require_recruiting(group)?;
+
+        let is_participant = group.participants.iter().any(|p| p.pubkey == participant);
+        require!(is_participant, KooPaaError::NotParticipant);
+
+        // A smaller group still has to be a valid group
+        require!(
+            group.participants.len() >= global_state.limits.min_participants as usize,
+            KooPaaError::InvalidParticipantCount
+        );
+
+        let already_voted = group.start_votes.contains(&participant);
+        if !already_voted {
+            group.start_votes.push(participant);
+        }
+
+        // Anyone who joins after voting began has to agree as well
+        let unanimous = group
+            .participants
+            .iter()
+            .all(|p| group.start_votes.contains(&p.pubkey));
+
+        // Voting again only helps once the last holdout has left the group
+        require!(
+            !already_voted || unanimous,
+            KooPaaError::AlreadyVotedToStart
+        );
+
+        if unanimous {
+            let planned_participants = group.num_participants;
+            group.num_participants = group.participants.len() as u8;
+            start_group(group, global_state, &ctx.accounts.slot_hashes, &clock)?;
+
+            emit!(GroupStartedEarlyEvent {
+                group_name: group.name.clone(),
+                num_participants: group.num_participants,
+                planned_participants,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn leave_ajo_group(ctx: Context<LeaveAjoGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant_key = ctx.accounts.participant.key();
+
+        require_recruiting(group)?;
+
+        // The creator's way out is cancelling the whole group
+        require!(
+            participant_key != group.creator,
+            KooPaaError::CreatorCannotLeave
+        );
+
+        let participant_index = group
+            .participants
+            .iter()
+            .position(|p| p.pubkey == participant_key)
+            .ok_or(KooPaaError::NotParticipant)?;
+
+        let participant = group.participants.remove(participant_index);
+        group.start_votes.retain(|key| *key != participant_key);
+        group.close_votes.retain(|key| *key != participant_key);
+
+        // Credit still owed from an earlier cycle leaves with the member
+        let refund_amount = (participant.security_deposit + participant.credit)
+            .min(ctx.accounts.group_token_vault.amount);
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.participant_token_account.to_account_info(),
+            authority: group.to_account_info(),
+        };
+
+        // The group PDA is the vault authority
+        let group_name = group.name.clone();
+        let signer_seeds = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                &[signer_seeds],
+            ),
+            refund_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        emit!(ParticipantLeftEvent {
+            group_name,
+            participant: participant_key,
+            refund_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn contribute(ctx: Context<Contribute>) -> Result<()> {
+        contribute_rounds(ctx, 0)
+    }
+
+    pub fn contribute_ahead(ctx: Context<Contribute>, rounds: u8) -> Result<()> {
+        require!(rounds > 0, KooPaaError::InvalidPrepayment);
+        contribute_rounds(ctx, rounds)
+    }
+
+    pub fn claim_round(ctx: Context<ClaimRound>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let recipient = &ctx.accounts.recipient;
+        let clock = Clock::get()?;
+
+        // Check if the group is running
+        require_active(group)?;
+        apply_protocol_pause(group, &ctx.accounts.global_state, clock.unix_timestamp);
+
+        // Check if the payout order is known
+        require!(
+            payout_order_revealed(group),
+            KooPaaError::PayoutOrderNotRevealed
+        );
+
+        // Find the recipient in the group
+        let recipient_index = group
+            .participants
+            .iter()
+            .position(|p| p.pubkey == recipient.key())
+            .ok_or(KooPaaError::NotParticipant)?;
+
+        require!(
+            group.payout_round < payouts_due(group, clock.unix_timestamp),
+            KooPaaError::PayoutNotYetDue
+        );
+
+        // Check if this is the recipient's turn
+        require!(
+            current_recipient_index(group) == Some(recipient_index),
+            KooPaaError::NotCurrentRecipient
+        );
+        require!(
+            !group.participants[recipient_index].defaulted,
+            KooPaaError::MemberDefaulted
+        );
+
+        // Check if all participants have contributed for this round
+        require!(
+            payout_funded(group, group.payout_round),
+            KooPaaError::NotAllContributed
+        );
+
+        // Transfer the funds (handled in a separate payout instruction)
+        // This function just checks eligibility
+
+        Ok(())
+    }
+
+    pub fn payout(ctx: Context<Payout>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        apply_protocol_pause(group, global_state, clock.unix_timestamp);
+        require!(
+            group.payout_round < payouts_due(group, clock.unix_timestamp),
+            KooPaaError::PayoutNotYetDue
+        );
+        require!(
+            payout_order_revealed(group),
+            KooPaaError::PayoutOrderNotRevealed
+        );
+
+        let recipient_index =
+            current_recipient_index(group).ok_or(KooPaaError::NotCurrentRecipient)?;
+        let recipient_pubkey = group.participants[recipient_index].pubkey;
+
+        // Verify the recipient is the correct one
+        require!(
+            recipient_pubkey == ctx.accounts.recipient.key(),
+            KooPaaError::NotCurrentRecipient
+        );
+        require!(
+            !group.participants[recipient_index].defaulted,
+            KooPaaError::MemberDefaulted
+        );
+
+        // The pot is only ever paid from contributions, never from deposits or prepayments
+        require!(
+            payout_funded(group, group.payout_round),
+            KooPaaError::NotAllContributed
+        );
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.recipient_token_account.to_account_info(),
+            authority: group.to_account_info(),
+        };
+
+        // Each seat contributes every round that falls due with this pot
+        let pot_amount = payout_pot(group, group.payout_round);
+
+        // Protocol fee is taken out of the pot before it reaches the recipient
+        let fee_amount = if global_state.fee_mode == FeeMode::OnPayout {
+            calculate_fee(pot_amount, global_state.fee_percentage)
+        } else {
+            0
+        };
+
+        // Auction winners take the pot minus their bid, shared equally by the other
+        // members, and every bid is cleared for the next round's auction
+        let discount = if group.payout_order == PayoutOrder::Auction {
+            // A fee raised, or a pot left short by a defaulter, since the bid was placed
+            // still leaves the pot whole
+            let bid = group.participants[recipient_index]
+                .bid
+                .min(max_payout_discount(group, global_state, pot_amount));
+            // Ejected members have no refund left to collect credit through
+            let sharing_members = group
+                .participants
+                .iter()
+                .enumerate()
+                .filter(|(index, p)| *index != recipient_index && !p.ejected)
+                .count() as u64;
+            let share = bid.checked_div(sharing_members).unwrap_or_default();
+            for (index, participant) in group.participants.iter_mut().enumerate() {
+                if index != recipient_index && !participant.ejected {
+                    participant.credit += share;
+                }
+                participant.bid = 0;
+            }
+            group.participants[recipient_index].claim_round = group.payout_round;
+            share * sharing_members
+        } else {
+            0
+        };
+
+        // Late penalties collected this round go to whoever receives the pot
+        let late_penalties = group.round_bonus;
+        group.round_bonus = 0;
+
+        let payout_amount = pot_amount
+            .checked_sub(fee_amount + discount)
+            .ok_or(KooPaaError::InvalidBid)?
+            + late_penalties;
+
+        // The group PDA is the vault authority
+        let group_name = group.name.clone();
+        let signer_seeds = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                &[signer_seeds],
+            ),
+            payout_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        if fee_amount > 0 {
+            let fee_accounts = TransferChecked {
+                from: ctx.accounts.group_token_vault.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: group.to_account_info(),
+            };
+
+            let treasury_balance_before = ctx.accounts.treasury_token_account.amount;
+
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    fee_accounts,
+                    &[signer_seeds],
+                ),
+                fee_amount,
+                ctx.accounts.token_mint.decimals,
+            )?;
+
+            // Revenue only counts what the treasury actually received
+            ctx.accounts.treasury_token_account.reload()?;
+            ctx.accounts.treasury_state.total_revenue +=
+                ctx.accounts.treasury_token_account.amount - treasury_balance_before;
+        }
+
+        // Deposits of members in default went into the pot in their place
+        let payout_round = group.payout_round;
+        let covers: Vec<u64> = group
+            .participants
+            .iter()
+            .map(|p| pot_share(group, p, payout_round).1)
+            .collect();
+        for (participant, covered) in group.participants.iter_mut().zip(covers) {
+            participant.covered += covered;
+        }
+
+        group.payout_round += 1;
+        group.participants[recipient_index].total_received += payout_amount;
+
+        emit!(PayoutMadeEvent {
+            group_name: group.name.clone(),
+            recipient: recipient_pubkey,
+            payout_amount,
+            fee_amount,
+            discount,
+            late_penalties,
+            payout_round: group.payout_round,
+        });
+
+        complete_if_paid_out(group, global_state)
+    }
+
+    // Nobody can be paid for a position voted out and never refilled. Once the payout
+    // after it falls due, anyone can skip it: whatever was paid into its pot is credited
+    // back to the members who paid it
+    pub fn skip_vacant_position(ctx: Context<SkipVacantPosition>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        apply_protocol_pause(group, global_state, clock.unix_timestamp);
+        require!(
+            payout_order_revealed(group),
+            KooPaaError::PayoutOrderNotRevealed
+        );
+
+        let vacant_index = current_recipient_index(group)
+            .filter(|&index| group.participants[index].ejected)
+            .ok_or(KooPaaError::PositionNotVacant)?;
+
+        // Until then a replacement can still take the position over
+        require!(
+            clock.unix_timestamp >= payout_due_timestamp(group, group.payout_round + 1),
+            KooPaaError::VacancyNotYetSkippable
+        );
+
+        // Ejected members forfeit what they had paid in, slashed deposits stay held
+        let payout_round = group.payout_round;
+        let shares: Vec<u64> = group
+            .participants
+            .iter()
+            .map(|p| pot_share(group, p, payout_round).0)
+            .collect();
+        let mut refunded_pot = 0;
+        let mut forfeited = 0;
+        for (participant, paid) in group.participants.iter_mut().zip(shares) {
+            if participant.ejected {
+                forfeited += paid;
+            } else {
+                participant.credit += paid;
+                refunded_pot += paid;
+            }
+        }
+        group.penalty_pool += forfeited;
+        group.payout_round += 1;
+
+        emit!(VacantPositionSkippedEvent {
+            group_name: group.name.clone(),
+            position: group.participants[vacant_index].pubkey,
+            payout_round: group.payout_round,
+            refunded_pot,
+        });
+
+        complete_if_paid_out(group, global_state)
+    }
+
+    pub fn slash_defaulter(ctx: Context<SlashDefaulter>, member: Pubkey) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        apply_protocol_pause(group, &ctx.accounts.global_state, clock.unix_timestamp);
+
+        let participant_index = group
+            .participants
+            .iter()
+            .position(|p| p.pubkey == member)
+            .ok_or(KooPaaError::NotParticipant)?;
+
+        let rounds_overdue = overdue_rounds(
+            group,
+            &group.participants[participant_index],
+            clock.unix_timestamp,
+        );
+        require!(rounds_overdue > 0, KooPaaError::NotInDefault);
+
+        // The deposit is already in the vault, so taking it is pure accounting:
+        // it covers whatever the member owes the pot that earlier slashes did not
+        let owed = group.contribution_amount * rounds_overdue as u64;
+        let group_name = group.name.clone();
+        let participant = &mut group.participants[participant_index];
+        let slashed_amount = owed
+            .saturating_sub(participant.slashed)
+            .min(participant.security_deposit);
+        require!(slashed_amount > 0, KooPaaError::AlreadySlashed);
+
+        participant.security_deposit -= slashed_amount;
+        participant.slashed += slashed_amount;
+        participant.defaulted = true;
+
+        emit!(DefaulterSlashedEvent {
+            group_name,
+            defaulter: member,
+            slashed_by: ctx.accounts.caller.key(),
+            rounds_overdue,
+            slashed_amount,
+            remaining_deposit: participant.security_deposit,
+        });
+
+        Ok(())
+    }
+
+    pub fn vote_to_eject(ctx: Context<VoteToEject>, member: Pubkey) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let voter = ctx.accounts.voter.key();
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        apply_protocol_pause(group, &ctx.accounts.global_state, clock.unix_timestamp);
+
+        let is_participant = group
+            .participants
+            .iter()
+            .any(|p| p.pubkey == voter && !p.ejected);
+        require!(is_participant, KooPaaError::NotParticipant);
+        require!(voter != member, KooPaaError::CannotEjectSelf);
+
+        let candidate_index = group
+            .participants
+            .iter()
+            .position(|p| p.pubkey == member && !p.ejected)
+            .ok_or(KooPaaError::NotParticipant)?;
+
+        // Only members who were slashed and kept falling behind can be voted out
+        let ejectable = |group: &AjoGroup, index: usize| {
+            let candidate = &group.participants[index];
+            candidate.defaulted
+                && overdue_rounds(group, candidate, clock.unix_timestamp)
+                    >= AjoGroup::EJECT_OVERDUE_ROUNDS
+        };
+        require!(ejectable(group, candidate_index), KooPaaError::NotEjectable);
+
+        // One ejection is voted on at a time; a stale one is dropped once its
+        // candidate has caught up
+        if group.eject_candidate != Some(member) {
+            if let Some(pending) = group.eject_candidate {
+                let pending_index = group.participants.iter().position(|p| p.pubkey == pending);
+                require!(
+                    !pending_index.is_some_and(|index| ejectable(group, index)),
+                    KooPaaError::EjectionInProgress
+                );
+            }
+            group.eject_candidate = Some(member);
+            group.eject_votes = vec![];
+        }
+
+        require!(
+            !group.eject_votes.contains(&voter),
+            KooPaaError::AlreadyVotedToEject
+        );
+        group.eject_votes.push(voter);
+
+        // The candidate has no say, so a majority of everyone else is needed
+        let eligible_voters = group.participants.iter().filter(|p| !p.ejected).count() - 1;
+        let total_votes = group.eject_votes.len();
+
+        if total_votes * 2 > eligible_voters {
+            let group_name = group.name.clone();
+            let candidate = &mut group.participants[candidate_index];
+
+            // Whatever deposit and auction credit the member had left is forfeited, along
+            // with the slashed deposit that has not gone into a pot yet
+            let forfeited_deposit = candidate.security_deposit
+                + candidate.credit
+                + (candidate.slashed - candidate.covered);
+            candidate.security_deposit = 0;
+            candidate.credit = 0;
+            candidate.slashed = candidate.covered;
+            candidate.bid = 0;
+            candidate.ejected = true;
+
+            group.penalty_pool += forfeited_deposit;
+            group.close_votes.retain(|key| *key != member);
+            group.eject_candidate = None;
+            group.eject_votes = vec![];
+
+            emit!(ParticipantEjectedEvent {
+                group_name,
+                participant: member,
+                forfeited_deposit,
+                total_votes: total_votes as u8,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn admit_replacement(ctx: Context<AdmitReplacement>, member: Pubkey) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+        let replacement = ctx.accounts.replacement.key();
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        apply_protocol_pause(group, global_state, clock.unix_timestamp);
+
+        let already_joined = group.participants.iter().any(|p| p.pubkey == replacement);
+        require!(!already_joined, KooPaaError::AlreadyJoined);
+
+        let position_index = group
+            .participants
+            .iter()
+            .position(|p| p.pubkey == member && p.ejected)
+            .ok_or(KooPaaError::PositionNotVacant)?;
+
+        // Taking over a position that was already paid out would mean paying in for nothing
+        let claim_round = unpaid_claim_round(group, &member)?;
+
+        // The replacement pays every round the position owes up to now, plus a joiner deposit
+        let current_round = current_contribution_round(group, clock.unix_timestamp);
+        let position = &group.participants[position_index];
+        let rounds_owed = current_round.saturating_sub(position.contribution_round);
+        let arrears = group.contribution_amount * rounds_owed as u64;
+        let security_deposit = scale_to_decimals(
+            global_state.joiner_security_deposit,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let gross_amount = gross_up_for_transfer_fee(
+            &ctx.accounts.token_mint.to_account_info(),
+            arrears + security_deposit,
+        )?;
+        require!(
+            ctx.accounts.replacement_token_account.amount >= gross_amount,
+            KooPaaError::InsufficientFunds
+        );
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.replacement_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.group_token_vault.to_account_info(),
+            authority: ctx.accounts.replacement.to_account_info(),
+        };
+
+        let vault_balance_before = ctx.accounts.group_token_vault.amount;
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            gross_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Arrears are covered first, whatever else landed is the deposit
+        ctx.accounts.group_token_vault.reload()?;
+        let received_amount = ctx.accounts.group_token_vault.amount - vault_balance_before;
+        require!(received_amount > arrears, KooPaaError::TransferFeeShortfall);
+        let security_deposit = received_amount - arrears;
+
+        // Arrears for pots already paid out go to their recipients, except what the
+        // forfeited deposit had put in, which is shared out with the penalties
+        let last_paid_round = group.participants[position_index].contribution_round;
+        let released_slash =
+            settle_late_rounds(group, position_index, last_paid_round + 1, current_round);
+
+        let group_name = group.name.clone();
+        let position = &mut group.participants[position_index];
+        position.pubkey = replacement;
+        position.contribution_round = position.contribution_round.max(current_round);
+        position.security_deposit = security_deposit;
+        position.total_contributed = arrears;
+        position.total_received = 0;
+        position.slashed = 0;
+        position.covered = 0;
+        position.defaulted = false;
+        position.ejected = false;
+        position.refunded = false;
+        group.penalty_pool += released_slash;
+
+        emit!(ReplacementAdmittedEvent {
+            group_name,
+            replaced: member,
+            replacement,
+            claim_round,
+            arrears,
+            security_deposit,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_payout_order(ctx: Context<SetPayoutOrder>, fixed_order: Vec<Pubkey>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+
+        require_recruiting(group)?;
+        require!(
+            group.payout_order == PayoutOrder::Fixed,
+            KooPaaError::PayoutOrderMismatch
+        );
+        require!(
+            fixed_order.len() <= group.num_participants as usize,
+            KooPaaError::InvalidParticipantCount
+        );
+
+        let has_duplicates = fixed_order
+            .iter()
+            .enumerate()
+            .any(|(i, key)| fixed_order[..i].contains(key));
+        require!(!has_duplicates, KooPaaError::DuplicatePayoutPosition);
+
+        // Members may be listed before they join; anyone unlisted is paid after them
+        group.fixed_order = fixed_order;
+
+        emit!(PayoutOrderSetEvent {
+            group_name: group.name.clone(),
+            fixed_order: group.fixed_order.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn place_payout_bid(ctx: Context<PlacePayoutBid>, discount: u64) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant_key = ctx.accounts.participant.key();
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        apply_protocol_pause(group, &ctx.accounts.global_state, clock.unix_timestamp);
+        require!(
+            group.payout_order == PayoutOrder::Auction,
+            KooPaaError::PayoutOrderMismatch
+        );
+
+        // Bids for a pot are taken until that pot is due to be paid out
+        require!(
+            clock.unix_timestamp < payout_due_timestamp(group, group.payout_round),
+            KooPaaError::BiddingClosed
+        );
+
+        // The discount can never eat into the protocol fee or the winner's own contribution
+        require!(
+            discount
+                <= max_payout_discount(
+                    group,
+                    &ctx.accounts.global_state,
+                    full_payout_pot(group, group.payout_round)
+                ),
+            KooPaaError::InvalidBid
+        );
+
+        let participant = group
+            .participants
+            .iter_mut()
+            .find(|p| p.pubkey == participant_key)
+            .ok_or(KooPaaError::NotParticipant)?;
+
+        // Members who already took a pot are out of the running
+        require!(
+            participant.claim_round == AjoParticipant::UNASSIGNED_ROUND,
+            KooPaaError::PositionAlreadyPaid
+        );
+
+        participant.bid = discount;
+
+        emit!(PayoutBidPlacedEvent {
+            group_name: group.name.clone(),
+            participant: participant_key,
+            discount,
+        });
+
+        Ok(())
+    }
+
+    pub fn reveal_payout_order(ctx: Context<RevealPayoutOrder>) -> Result<()> {
+        let group_key = ctx.accounts.ajo_group.key();
+        let group = &mut ctx.accounts.ajo_group;
+        let clock = Clock::get()?;
+
+        require_active(group)?;
+        require!(
+            group.payout_order == PayoutOrder::VerifiableRandom,
+            KooPaaError::PayoutOrderMismatch
+        );
+        require!(
+            group.payout_seed.is_none(),
+            KooPaaError::PayoutOrderAlreadyRevealed
+        );
+
+        let reveal_slot = group.order_commit_slot + AjoGroup::REVEAL_DELAY_SLOTS;
+        match find_reveal_hash(&ctx.accounts.slot_hashes, reveal_slot)? {
+            RevealHash::Pending => return err!(KooPaaError::RevealNotReady),
+            RevealHash::Expired => commit_payout_order(group, clock.slot),
+            RevealHash::Ready(slot_hash) => {
+                let seed = reveal_seed(&slot_hash, &group_key);
+                assign_claim_rounds(group, &seed);
+                group.payout_seed = Some(seed);
+
+                emit!(PayoutScheduleEvent {
+                    group_name: group.name.clone(),
+                    payout_order: group.payout_order,
+                    payout_seed: group.payout_seed,
+                    schedule: payout_schedule(group),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn propose_swap(
+        ctx: Context<ProposeSwap>,
+        counterparty: Pubkey,
+        sweetener: u64,
+    ) -> Result<()> {
+        let group = &ctx.accounts.ajo_group;
+        let proposer = ctx.accounts.proposer.key();
+
+        require_active(group)?;
+        require!(
+            payout_order_revealed(group),
+            KooPaaError::PayoutOrderNotRevealed
+        );
+        require!(
+            group.payout_order != PayoutOrder::Auction,
+            KooPaaError::PayoutOrderMismatch
+        );
+        require!(proposer != counterparty, KooPaaError::CannotSwapWithSelf);
+
+        // Positions that have already been paid out can never change hands
+        let proposer_claim_round = unpaid_claim_round(group, &proposer)?;
+        let counterparty_claim_round = unpaid_claim_round(group, &counterparty)?;
+
+        // The sweetener waits in escrow until the counterparty accepts or the offer is cancelled
+        if sweetener > 0 {
+            let transfer_accounts = TransferChecked {
+                from: ctx.accounts.proposer_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.swap_escrow.to_account_info(),
+                authority: ctx.accounts.proposer.to_account_info(),
+            };
+
+            transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    transfer_accounts,
+                ),
+                sweetener,
+                ctx.accounts.token_mint.decimals,
+            )?;
+
+            ctx.accounts.swap_escrow.reload()?;
+        }
+
+        let proposal = &mut ctx.accounts.swap_proposal;
+
+        proposal.ajo_group = group.key();
+        proposal.proposer = proposer;
+        proposal.counterparty = counterparty;
+        proposal.proposer_claim_round = proposer_claim_round;
+        proposal.counterparty_claim_round = counterparty_claim_round;
+        proposal.sweetener = ctx.accounts.swap_escrow.amount;
+        proposal.bumps = ctx.bumps.swap_proposal;
+
+        emit!(SwapProposedEvent {
+            group_name: group.name.clone(),
+            proposer,
+            counterparty,
+            proposer_claim_round,
+            counterparty_claim_round,
+            sweetener: proposal.sweetener,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_swap(ctx: Context<AcceptSwap>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let proposal = &ctx.accounts.swap_proposal;
+
+        require_active(group)?;
+
+        // Both positions must still be unpaid and exactly what was offered
+        let proposer_claim_round = unpaid_claim_round(group, &proposal.proposer)?;
+        let counterparty_claim_round = unpaid_claim_round(group, &proposal.counterparty)?;
+        require!(
+            proposer_claim_round == proposal.proposer_claim_round
+                && counterparty_claim_round == proposal.counterparty_claim_round,
+            KooPaaError::SwapPositionsChanged
+        );
+
+        for participant in group.participants.iter_mut() {
+            if participant.pubkey == proposal.proposer {
+                participant.claim_round = counterparty_claim_round;
+            } else if participant.pubkey == proposal.counterparty {
+                participant.claim_round = proposer_claim_round;
+            }
+        }
+
+        release_swap_escrow(
+            proposal,
+            &ctx.accounts.swap_escrow,
+            &ctx.accounts.counterparty_token_account,
+            &ctx.accounts.proposer.to_account_info(),
+            &ctx.accounts.token_mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(SwapAcceptedEvent {
+            group_name: group.name.clone(),
+            proposer: proposal.proposer,
+            counterparty: proposal.counterparty,
+            proposer_claim_round: counterparty_claim_round,
+            counterparty_claim_round: proposer_claim_round,
+            sweetener: proposal.sweetener,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_swap(ctx: Context<CancelSwap>) -> Result<()> {
+        let proposal = &ctx.accounts.swap_proposal;
+
+        // The escrowed sweetener goes back to the proposer
+        release_swap_escrow(
+            proposal,
+            &ctx.accounts.swap_escrow,
+            &ctx.accounts.proposer_token_account,
+            &ctx.accounts.proposer.to_account_info(),
+            &ctx.accounts.token_mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(SwapCancelledEvent {
+            group_name: ctx.accounts.ajo_group.name.clone(),
+            proposer: proposal.proposer,
+            counterparty: proposal.counterparty,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_ajo_group(ctx: Context<CloseAjoGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant = &ctx.accounts.participant;
+        let global_state = &mut ctx.accounts.global_state;
+
+        match group.status {
+            GroupStatus::Completed => return err!(KooPaaError::GroupCompleted),
+            GroupStatus::Cancelled | GroupStatus::Settling | GroupStatus::Closed => {
+                return err!(KooPaaError::GroupAlreadyClosed)
+            }
+            _ => {}
+        }
+
+        // Check if the caller is a participant
+        let is_participant = group
+            .participants
+            .iter()
+            .any(|p| p.pubkey == participant.key() && !p.ejected);
+
+        require!(is_participant, KooPaaError::NotParticipant);
+
+        // Check if they've already voted
+        let already_voted = group.close_votes.contains(&participant.key());
+        if already_voted {
+            return err!(KooPaaError::AlreadyVotedToClose);
+        }
+
+        // Add their vote
+        group.close_votes.push(participant.key());
+
+        let total_participants = group.participants.len();
+        let total_votes = group.close_votes.len();
+
+        // If majority votes to close
+        if total_votes * 2 > total_participants {
+            // Security deposits are refunded through withdraw_security_deposit
+            if group.status == GroupStatus::Recruiting {
+                transition(group, GroupStatus::Cancelled)?;
+            } else {
+                transition(group, GroupStatus::Settling)?;
+                global_state.active_groups -= 1;
+            }
+
+            emit!(AjoGroupClosedEvent {
+                group_name: group.name.clone(),
+                total_votes: total_votes as u8,
+                group_size: total_participants as u8,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn cancel_ajo_group(ctx: Context<CancelAjoGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+
+        require_recruiting(group)?;
+
+        // Security deposits are refunded through withdraw_security_deposit
+        transition(group, GroupStatus::Cancelled)?;
+
+        emit!(AjoGroupCancelledEvent {
+            group_name: group.name.clone(),
+            group_size: group.participants.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    pub fn expire_group(ctx: Context<ExpireGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+
+        require_recruiting(group)?;
+
+        let join_deadline = group
+            .join_deadline
+            .ok_or(KooPaaError::JoinDeadlineNotReached)?;
+        require!(
+            clock.unix_timestamp > join_deadline,
+            KooPaaError::JoinDeadlineNotReached
+        );
+
+        // Security deposits are refunded through withdraw_security_deposit
+        transition(group, GroupStatus::Cancelled)?;
+        global_state.expired_groups += 1;
+
+        emit!(AjoGroupExpiredEvent {
+            group_name: group.name.clone(),
+            group_size: group.participants.len() as u8,
+            join_deadline,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+        let global_info = ctx.accounts.global_state.to_account_info();
+        let admin = &ctx.accounts.admin;
+
+        // The global state is still in the legacy layout, which is smaller than a GlobalState
+        let legacy_state = {
+            let data = global_info.try_borrow_data()?;
+            require!(
+                data.len() < GlobalState::SIZE && data.starts_with(GlobalState::DISCRIMINATOR),
+                ErrorCode::AccountDidNotDeserialize
+            );
+            LegacyGlobalState::deserialize(&mut &data[8..])
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize)?
+        };
+        require!(
+            legacy_state.admin == admin.key(),
+            KooPaaError::OnlyAdminCanUpdate
+        );
+
+        let global_state = legacy_state.upgrade();
+        grow_account(
+            &global_info,
+            GlobalState::SIZE,
+            &admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        global_state.try_serialize(&mut &mut global_info.try_borrow_mut_data()?[..])?;
+
+        emit!(GlobalStateMigratedEvent {
+            admin: global_state.admin,
+            total_groups: global_state.total_groups,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_group_vault(ctx: Context<MigrateGroupVault>, name: String) -> Result<()> {
+        let group_info = ctx.accounts.ajo_group.to_account_info();
+        let creator = &ctx.accounts.creator;
+        let amount = ctx.accounts.legacy_vault.amount;
+
+        // The group is still in the legacy layout, which is smaller than any AjoGroup
+        let new_size = AjoGroup::calculate_size(&name);
+        let legacy_group = {
+            let data = group_info.try_borrow_data()?;
+            require!(
+                data.len() < new_size && data.starts_with(AjoGroup::DISCRIMINATOR),
+                ErrorCode::AccountDidNotDeserialize
+            );
+            LegacyAjoGroup::deserialize(&mut &data[8..])
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize)?
+        };
+        require!(
+            legacy_group.participants.first().map(|p| p.pubkey) == Some(creator.key()),
+            KooPaaError::InvalidVaultAuthority
+        );
+
+        // A zero interval can never be scheduled, whatever the group is upgraded to
+        require!(
+            legacy_group.contribution_interval > 0 && legacy_group.payout_interval > 0,
+            KooPaaError::InvalidInterval
+        );
+
+        // Move everything the creator-owned legacy vault holds into the PDA-owned vault
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.legacy_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.group_token_vault.to_account_info(),
+            authority: ctx.accounts.creator.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Close the emptied legacy vault and return its rent to the creator
+        let close_accounts = CloseAccount {
+            account: ctx.accounts.legacy_vault.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: ctx.accounts.creator.to_account_info(),
+        };
+
+        close_account(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            close_accounts,
+        ))?;
+
+        // Rewrite the group in the current layout; pauses before now are not its concern
+        let mut group = legacy_group.upgrade(
+            ctx.accounts.token_mint.key(),
+            ctx.accounts.global_state.joiner_security_deposit,
+        );
+        group.protocol_paused_seconds = ctx
+            .accounts
+            .global_state
+            .paused_seconds_at(Clock::get()?.unix_timestamp);
+
+        // Contribution rounds are counted in a u8 over the whole cycle
+        let cycle_rounds = group.participants.len() as u32
+            * (group.payout_interval / group.contribution_interval) as u32;
+        require!(
+            cycle_rounds <= u8::MAX as u32,
+            KooPaaError::TooManyContributionRounds
+        );
+
+        grow_account(
+            &group_info,
+            new_size,
+            &creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        group.try_serialize(&mut &mut group_info.try_borrow_mut_data()?[..])?;
+
+        emit!(GroupVaultMigratedEvent {
+            group_name: name,
+            legacy_vault: ctx.accounts.legacy_vault.key(),
+            group_vault: ctx.accounts.group_token_vault.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_security_deposit(ctx: Context<WithdrawSecurityDeposit>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant_key = ctx.accounts.participant.key();
+
+        // Deposits are only released once the group has been wound down or every round has paid out
+        require!(group.status.is_settled(), KooPaaError::GroupNotSettled);
+
+        // Members carrying on into the next cycle keep their deposit in the vault
+        require!(
+            !group.next_cycle_members.contains(&participant_key),
+            KooPaaError::OptedIntoNextCycle
+        );
+
+        // Get all values we need before the mutable borrow
+        let group_name = group.name.clone();
+
+        // Pooled penalties, and any bonus no payout claimed, are shared by the members left
+        let members_left = group.participants.iter().filter(|p| !p.ejected).count() as u64;
+        let penalty_share = (group.penalty_pool + group.round_bonus) / members_left;
+
+        let participant_index = group
+            .participants
+            .iter()
+            .position(|p| p.pubkey == participant_key)
+            .ok_or(KooPaaError::NotParticipant)?;
+        let unspent_contributions =
+            calculate_unspent_contributions(group, &group.participants[participant_index]);
+
+        let participant = &mut group.participants[participant_index];
+        require!(!participant.refunded, KooPaaError::AlreadyRefunded);
+        require!(!participant.ejected, KooPaaError::MemberEjected);
+
+        let security_deposit = participant.security_deposit;
+        let credit = participant.credit;
+
+        // Never try to move more than the vault holds
+        let refund_amount = (security_deposit + unspent_contributions + credit + penalty_share)
+            .min(ctx.accounts.group_token_vault.amount);
+
+        participant.refunded = true;
+
+        // Nothing is left to settle once every remaining member has been refunded
+        if group.participants.iter().all(|p| p.refunded || p.ejected) {
+            transition(group, GroupStatus::Closed)?;
+        }
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.participant_token_account.to_account_info(),
+            authority: group.to_account_info(),
+        };
+
+        // The group PDA is the vault authority
+        let signer_seeds = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                &[signer_seeds],
+            ),
+            refund_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        emit!(SecurityDepositWithdrawnEvent {
+            group_name,
+            participant: participant_key,
+            security_deposit,
+            unspent_contributions,
+            credit,
+            penalty_share,
+            refund_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn opt_into_next_cycle(ctx: Context<OptIntoNextCycle>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let member = ctx.accounts.participant.key();
+
+        require!(
+            group.status == GroupStatus::Completed,
+            KooPaaError::CycleNotCompleted
+        );
+
+        let participant = group
+            .participants
+            .iter()
+            .find(|p| p.pubkey == member && !p.ejected)
+            .ok_or(KooPaaError::NotParticipant)?;
+        require!(!participant.refunded, KooPaaError::AlreadyRefunded);
+        require!(
+            !group.next_cycle_members.contains(&member),
+            KooPaaError::AlreadyOptedIn
+        );
+
+        group.next_cycle_members.push(member);
+
+        emit!(OptedIntoNextCycleEvent {
+            group_name: group.name.clone(),
+            member,
+            cycle: group.cycle + 1,
+        });
+
+        Ok(())
+    }
+
+    // Members who change their mind take their deposit back through withdraw_security_deposit
+    pub fn opt_out_of_next_cycle(ctx: Context<OptOutOfNextCycle>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let member = ctx.accounts.participant.key();
+
+        require!(
+            group.status == GroupStatus::Completed,
+            KooPaaError::CycleNotCompleted
+        );
+
+        let opt_in = group
+            .next_cycle_members
+            .iter()
+            .position(|key| *key == member)
+            .ok_or(KooPaaError::NotOptedIn)?;
+        group.next_cycle_members.remove(opt_in);
+
+        emit!(OptedOutOfNextCycleEvent {
+            group_name: group.name.clone(),
+            member,
+            cycle: group.cycle + 1,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_next_cycle_terms(
+        ctx: Context<SetNextCycleTerms>,
+        contribution_amount: u64,
+        payout_order: PayoutOrder,
+    ) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+
+        require!(
+            group.status == GroupStatus::Completed,
+            KooPaaError::CycleNotCompleted
+        );
+        require!(
+            contribution_amount > 0,
+            KooPaaError::InvalidContributionAmount
+        );
+
+        // Members opted in to the terms as they stood, so they have to opt in again
+        let terms = CycleTerms {
+            contribution_amount,
+            payout_order,
+        };
+        let opted_in = group.next_cycle_members.len();
+        if terms != group.terms_for_next_cycle() {
+            let creator = group.creator;
+            group.next_cycle_members.retain(|key| *key == creator);
+        }
+        group.next_cycle_terms = Some(terms);
+
+        emit!(NextCycleTermsSetEvent {
+            group_name: group.name.clone(),
+            cycle: group.cycle + 1,
+            contribution_amount,
+            payout_order,
+            opt_ins_cleared: (opted_in - group.next_cycle_members.len()) as u8,
+        });
+
+        Ok(())
+    }
+
+    // Rolls the group over on the terms members opted in to
+    pub fn start_next_cycle(
+        ctx: Context<StartNextCycle>,
+        join_deadline: Option<i64>,
+    ) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let creator = ctx.accounts.creator.key();
+        let clock = Clock::get()?;
+
+        require!(
+            group.status == GroupStatus::Completed,
+            KooPaaError::CycleNotCompleted
+        );
+        require!(
+            join_deadline.is_none_or(|deadline| deadline > clock.unix_timestamp),
+            KooPaaError::InvalidJoinDeadline
+        );
+
+        // Rolling the group over is the creator opting in, so they have to still hold a seat
+        let creator_seat = group
+            .participants
+            .iter()
+            .find(|p| p.pubkey == creator)
+            .ok_or(KooPaaError::NotParticipant)?;
+        require!(!creator_seat.ejected, KooPaaError::MemberEjected);
+        require!(!creator_seat.refunded, KooPaaError::AlreadyRefunded);
+        if !group.next_cycle_members.contains(&creator) {
+            group.next_cycle_members.push(creator);
+        }
+
+        // Everyone else has either opted in or taken their deposit back
+        let settled = group
+            .participants
+            .iter()
+            .all(|p| p.ejected || p.refunded || group.next_cycle_members.contains(&p.pubkey));
+        require!(settled, KooPaaError::NextCycleNotSettled);
+
+        // Members who withdrew already took their share of the pooled penalties
+        let members_left = group.participants.iter().filter(|p| !p.ejected).count() as u64;
+        let penalty_share = (group.penalty_pool + group.round_bonus) / members_left;
+        let refunded = group
+            .participants
+            .iter()
+            .filter(|p| p.refunded && !p.ejected)
+            .count() as u64;
+        group.penalty_pool = group.penalty_pool + group.round_bonus - penalty_share * refunded;
+        group.round_bonus = 0;
+
+        // Deposits and unclaimed credit carry over; everything else starts afresh. Slashed
+        // deposits were already settled when the cycle completed
+        let next_cycle_members = std::mem::take(&mut group.next_cycle_members);
+        group
+            .participants
+            .retain(|p| next_cycle_members.contains(&p.pubkey));
+        for participant in group.participants.iter_mut() {
+            participant.claim_round = 0;
+            participant.contribution_round = 0;
+            participant.bid = 0;
+            participant.defaulted = false;
+            participant.ejected = false;
+            participant.total_contributed = 0;
+            participant.total_received = 0;
+        }
+
+        let terms = group.terms_for_next_cycle();
+        group.contribution_amount = terms.contribution_amount;
+        group.payout_order = terms.payout_order;
+        group.next_cycle_terms = None;
+        group.join_deadline = join_deadline;
+        group.fixed_order = vec![];
+        group.order_commit_slot = 0;
+        group.payout_seed = None;
+        group.payout_round = 0;
+        group.start_timestamp = None;
+        group.start_votes = vec![];
+        group.close_votes = vec![];
+        group.eject_candidate = None;
+        group.eject_votes = vec![];
+        group.cycle += 1;
+
+        // Open slots are refilled through join_ajo_group, or the members left vote to start
+        transition(group, GroupStatus::Recruiting)?;
+
+        emit!(NextCycleStartedEvent {
+            group_name: group.name.clone(),
+            cycle: group.cycle,
+            contribution_amount: group.contribution_amount,
+            payout_order: group.payout_order,
+            members: group.participants.len() as u8,
+            open_slots: group.num_participants - group.participants.len() as u8,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = GlobalState::SIZE,
+        seeds = [b"global-state"],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminUpdate<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        has_one = admin @ KooPaaError::OnlyAdminCanUpdate
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = global_state.pending_admin == Some(new_admin.key()) @ KooPaaError::NotPendingAdmin
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GroupAdminUpdate<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        has_one = admin @ KooPaaError::OnlyAdminCanUpdate
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+}
+
+#[derive(Accounts)]
+pub struct AddAllowedMint<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        has_one = admin @ KooPaaError::OnlyAdminCanUpdate
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AllowedMint::SIZE,
+        seeds = [b"allowed-mint", token_mint.key().as_ref()],
+        bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowedMint<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        has_one = admin @ KooPaaError::OnlyAdminCanUpdate
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"allowed-mint", allowed_mint.mint.as_ref()],
+        bump = allowed_mint.bumps
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        has_one = admin @ KooPaaError::OnlyAdminCanUpdate
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = global_state,
+        token::token_program = token_program
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = TreasuryState::SIZE,
+        seeds = [b"treasury-state", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_state: Account<'info, TreasuryState>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = authority.key() == global_state.admin
+            || Some(authority.key()) == global_state.treasury_multisig
+            @ KooPaaError::UnauthorizedTreasuryWithdrawal
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury-state", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump = treasury_state.bumps
+    )]
+    pub treasury_state: Account<'info, TreasuryState>,
+
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == token_mint.key()
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    name: String,
+    contribution_amount: u64,
+    contribution_interval: u16,
+    payout_interval: u16,
+    num_participants: u8
+)]
+pub struct CreateAjoGroup<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = AjoGroup::calculate_size(&name),
+        seeds = [b"ajo-group", group_name_seed(&name)?],
+        bump
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [b"allowed-mint", token_mint.key().as_ref()],
+        bump = allowed_mint.bumps
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key(),
+        constraint = creator_token_account.mint == token_mint.key()
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = ajo_group,
+        token::token_program = token_program
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct JoinAjoGroup<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = participant_token_account.owner == participant.key(),
+        constraint = participant_token_account.mint == token_mint.key()
+    )]
+    pub participant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: pinned to the SlotHashes sysvar, only the latest hash is read
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteStartEarly<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: pinned to the SlotHashes sysvar, only the latest hash is read
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveAjoGroup<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = participant_token_account.owner == participant.key(),
+        constraint = participant_token_account.mint == token_mint.key()
+    )]
+    pub participant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub contributor: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = contributor_token_account.owner == contributor.key(),
+        constraint = contributor_token_account.mint == token_mint.key(),
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury-state", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump = treasury_state.bumps
+    )]
+    pub treasury_state: Account<'info, TreasuryState>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRound<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub recipient: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Payout<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == recipient.key(),
+        constraint = recipient_token_account.mint == token_mint.key()
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury-state", global_state.key().as_ref(), token_mint.key().as_ref()],
+        bump = treasury_state.bumps
+    )]
+    pub treasury_state: Account<'info, TreasuryState>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SkipVacantPosition<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct SlashDefaulter<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VoteToEject<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct AdmitReplacement<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub replacement: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = replacement_token_account.owner == replacement.key(),
+        constraint = replacement_token_account.mint == token_mint.key()
+    )]
+    pub replacement_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutOrder<'info> {
+    #[account(
+        mut,
+        has_one = creator @ KooPaaError::OnlyCreator
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlacePayoutBid<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct RevealPayoutOrder<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    /// CHECK: pinned to the SlotHashes sysvar, entries are read manually
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeSwap<'info> {
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = SwapProposal::SIZE,
+        seeds = [b"swap", ajo_group.key().as_ref(), proposer.key().as_ref()],
+        bump
+    )]
+    pub swap_proposal: Account<'info, SwapProposal>,
+
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [b"swap-escrow", swap_proposal.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = swap_proposal,
+        token::token_program = token_program
+    )]
+    pub swap_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = proposer_token_account.owner == proposer.key(),
+        constraint = proposer_token_account.mint == token_mint.key()
+    )]
+    pub proposer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptSwap<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"swap", ajo_group.key().as_ref(), proposer.key().as_ref()],
+        bump = swap_proposal.bumps,
+        has_one = ajo_group,
+        has_one = proposer,
+        has_one = counterparty @ KooPaaError::NotSwapCounterparty,
+        close = proposer
+    )]
+    pub swap_proposal: Account<'info, SwapProposal>,
+
+    #[account(
+        mut,
+        seeds = [b"swap-escrow", swap_proposal.key().as_ref()],
+        bump
+    )]
+    pub swap_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    pub counterparty: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = counterparty_token_account.owner == counterparty.key(),
+        constraint = counterparty_token_account.mint == token_mint.key()
+    )]
+    pub counterparty_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Receives the proposal and escrow rent
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSwap<'info> {
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"swap", ajo_group.key().as_ref(), proposer.key().as_ref()],
+        bump = swap_proposal.bumps,
+        has_one = ajo_group,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub swap_proposal: Account<'info, SwapProposal>,
+
+    #[account(
+        mut,
+        seeds = [b"swap-escrow", swap_proposal.key().as_ref()],
+        bump
+    )]
+    pub swap_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = proposer_token_account.owner == proposer.key(),
+        constraint = proposer_token_account.mint == token_mint.key()
+    )]
+    pub proposer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAjoGroup<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAjoGroup<'info> {
+    #[account(
+        mut,
+        has_one = creator @ KooPaaError::OnlyCreator
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireGroup<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    /// CHECK: still in the legacy layout, so the handler decodes and rewrites it by hand
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump,
+        owner = crate::ID
+    )]
+    pub global_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct MigrateGroupVault<'info> {
+    /// CHECK: still in the legacy layout, so the handler decodes and rewrites it by hand
+    #[account(
+        mut,
+        seeds = [b"ajo-group", name.as_bytes()],
+        bump,
+        owner = crate::ID
+    )]
+    pub ajo_group: UncheckedAccount<'info>,
+
+    // Vaults created before the seeds were unified were keyed by name and owned by the creator
+    #[account(
+        mut,
+        seeds = [b"group-vault", name.as_bytes()],
+        bump,
+        constraint = legacy_vault.owner == creator.key() @ KooPaaError::InvalidVaultAuthority
+    )]
+    pub legacy_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = ajo_group,
+        token::token_program = token_program
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = legacy_vault.mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecurityDeposit<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = participant_token_account.owner == participant.key(),
+        constraint = participant_token_account.mint == token_mint.key()
+    )]
+    pub participant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.token_mint @ KooPaaError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct OptIntoNextCycle<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OptOutOfNextCycle<'info> {
+    #[account(mut)]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetNextCycleTerms<'info> {
+    #[account(
+        mut,
+        has_one = creator @ KooPaaError::OnlyCreator
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartNextCycle<'info> {
+    #[account(
+        mut,
+        has_one = creator @ KooPaaError::OnlyCreator
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        constraint = !global_state.paused @ KooPaaError::ProtocolPaused
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
@@ -0,0 +1,157 @@
+use crate::state::*;
+use crate::utils::days_to_seconds;
+
+// Contributions are counted in rounds of contribution_interval and payouts in rounds of
+// payout_interval. A payout interval always spans a whole number of contribution rounds,
+// so every pot is funded by exactly the contribution rounds that fall due with it. Each
+// round is open for a whole contribution interval before the payout it funds.
+
+// When a contribution round (counted from 1) can be paid, is expected and becomes late
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RoundWindow {
+    pub opens_at: i64,   // The round becomes payable
+    pub due_at: i64,     // The payout this round funds falls due
+    pub late_after: i64, // Grace period has run out
+}
+
+// Contribution rounds that go into each pot
+pub fn rounds_per_payout(group: &AjoGroup) -> u8 {
+    (group.payout_interval / group.contribution_interval) as u8
+}
+
+// What each member pays towards a single pot
+pub fn contribution_per_payout(group: &AjoGroup) -> u64 {
+    group.contribution_amount * rounds_per_payout(group) as u64
+}
+
+// Contribution rounds in a full cycle, one pot per participant
+pub fn total_contribution_rounds(group: &AjoGroup) -> u8 {
+    group.participants.len() as u8 * rounds_per_payout(group)
+}
+
+// Contribution rounds every member must have paid before payout `payout_round` (from 0)
+pub fn rounds_funding_payout(group: &AjoGroup, payout_round: u8) -> u8 {
+    (payout_round + 1) * rounds_per_payout(group)
+}
+
+// What a participant puts into payout `payout_round`: the rounds they paid towards it,
+// and for members in default whatever their slashed deposit still covers of the rest.
+// Ejected members only ever put in what they had paid
+pub fn pot_share(group: &AjoGroup, participant: &AjoParticipant, payout_round: u8) -> (u64, u64) {
+    let funding_rounds = rounds_funding_payout(group, payout_round);
+    let first_round = funding_rounds - rounds_per_payout(group);
+    let paid_rounds = participant
+        .contribution_round
+        .clamp(first_round, funding_rounds)
+        - first_round;
+    let paid = group.contribution_amount * paid_rounds as u64;
+
+    let covered = if participant.defaulted && !participant.ejected {
+        let shortfall = contribution_per_payout(group) - paid;
+        shortfall.min(participant.slashed - participant.covered)
+    } else {
+        0
+    };
+
+    (paid, covered)
+}
+
+// Pot paid out for payout `payout_round`, before fees and auction discounts
+pub fn payout_pot(group: &AjoGroup, payout_round: u8) -> u64 {
+    group
+        .participants
+        .iter()
+        .map(|p| {
+            let (paid, covered) = pot_share(group, p, payout_round);
+            paid + covered
+        })
+        .sum()
+}
+
+// Pot for payout `payout_round` once every member still contributing has paid in full
+pub fn full_payout_pot(group: &AjoGroup, payout_round: u8) -> u64 {
+    group
+        .participants
+        .iter()
+        .map(|p| {
+            if p.ejected {
+                pot_share(group, p, payout_round).0
+            } else {
+                contribution_per_payout(group)
+            }
+        })
+        .sum()
+}
+
+// Whether every member still contributing has paid in for payout `payout_round`, or
+// been slashed so their deposit stands in for what they missed
+pub fn payout_funded(group: &AjoGroup, payout_round: u8) -> bool {
+    let funding_rounds = rounds_funding_payout(group, payout_round);
+    group
+        .participants
+        .iter()
+        .filter(|p| !p.ejected)
+        .all(|p| p.contribution_round >= funding_rounds || p.defaulted)
+}
+
+// Pauses stop a running group's clock: its schedule is moved back by whatever time the
+// protocol has spent paused since it was last brought up to date. Time the group spent
+// paused itself is made up when it resumes instead
+pub fn apply_protocol_pause(group: &mut AjoGroup, global_state: &GlobalState, now: i64) {
+    let paused_seconds = global_state.paused_seconds_at(now);
+    if group.status != GroupStatus::Paused {
+        if let Some(start) = group.start_timestamp.as_mut() {
+            *start += paused_seconds - group.protocol_paused_seconds;
+        }
+    }
+    group.protocol_paused_seconds = paused_seconds;
+}
+
+pub fn contribution_window(group: &AjoGroup, round: u8) -> RoundWindow {
+    let start = group.start_timestamp.unwrap_or_default();
+    let opens_at =
+        start + days_to_seconds(group.contribution_interval) * round.saturating_sub(1) as i64;
+
+    // A round is due with the first payout that needs it
+    let rounds_per_payout = rounds_per_payout(group);
+    let funded_payout = round.saturating_sub(1) / rounds_per_payout;
+    let due_at = payout_due_timestamp(group, funded_payout);
+
+    RoundWindow {
+        opens_at,
+        due_at,
+        late_after: due_at + group.late_policy.grace_period_hours as i64 * 60 * 60,
+    }
+}
+
+// Timestamp payout `payout_round` (from 0) can be made at
+pub fn payout_due_timestamp(group: &AjoGroup, payout_round: u8) -> i64 {
+    group.start_timestamp.unwrap_or_default()
+        + days_to_seconds(group.payout_interval) * (payout_round as i64 + 1)
+}
+
+// Contribution rounds that have opened by `now`, never past the end of the cycle.
+// The first round opens as soon as the group starts
+pub fn current_contribution_round(group: &AjoGroup, now: i64) -> u8 {
+    let Some(start) = group.start_timestamp else {
+        return 0;
+    };
+    let opened_rounds = (now - start) / days_to_seconds(group.contribution_interval) + 1;
+    opened_rounds.clamp(0, total_contribution_rounds(group) as i64) as u8
+}
+
+// Rounds a participant has paid for that have not opened by `now`
+pub fn prepaid_rounds(group: &AjoGroup, participant: &AjoParticipant, now: i64) -> u8 {
+    participant
+        .contribution_round
+        .saturating_sub(current_contribution_round(group, now))
+}
+
+// Payouts that have fallen due by `now`, never more than one per participant
+pub fn payouts_due(group: &AjoGroup, now: i64) -> u8 {
+    let Some(start) = group.start_timestamp else {
+        return 0;
+    };
+    let elapsed_payouts = (now - start) / days_to_seconds(group.payout_interval);
+    elapsed_payouts.clamp(0, group.participants.len() as i64) as u8
+}
@@ -1,86 +1,569 @@
-use anchor_lang::prelude::*;
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct AjoParticipant {
-    pub pubkey: Pubkey,
-    pub claim_round: u8,
-    pub contribution_round: u8,
-    pub bump: u8,
-}
-
-#[account]
-pub struct AjoGroup {
-    // Basic group information
-    pub name: String,               // Unique name for the group
-    pub security_deposit: u64,      // Amount in USDC to join this group
-    pub contribution_amount: u64,   // Amount in USDC to contribute each round
-    pub contribution_interval: u16, // Time between rounds when a user should pay (in days)
-    pub payout_interval: u16,       // Time between payouts (in days)
-    pub num_participants: u8,       // Total number of participants needed
-
-    // Participants and round management
-    pub participants: Vec<AjoParticipant>, // List of all participants (ordered by join time)
-    pub start_timestamp: Option<i64>,
-    pub payout_round: u8, // state for payouts made, useful in calc current round, index of recipient
-
-    pub close_votes: Vec<Pubkey>, // Track who has voted to close
-    pub is_closed: bool,
-
-    pub bumps: u8, // PDA bump
-}
-
-impl AjoGroup {
-    // Calculate space required for account
-    pub fn calculate_size(name: &str) -> usize {
-        // Space for fixed fields
-        let fixed_size = 8 +  // account discriminator
-                         (4 + name.len()) +  // name (string)
-                         8 +  // contribution_amount (u64)
-                         2 +  // interval_in_days (u16)
-                         1 +  // num_participants (u8)
-                         32 + // creator (Pubkey)
-                         4 +  // participants vector length
-                         1 +  // current_round (u8)
-                         1 +  // current_receiver_index (u8)
-                         1 +  // started (bool)
-                         1 +  // completed (bool)
-                         8 +  // total_distributed (u64)
-                         8 +  // last_round_timestamp (i64)
-                         1; // bumps (u8)
-
-        // Space for participants (with all their data)
-        // Each participant has: pubkey (32) + turn_number (1) + claim_time (8) +
-        // claimed (1) + claim_round (1) + rounds_contributed vec (4 + num_participants) +
-        // claim_amount (8)
-        let participant_size = 32 + 1 + 8 + 1 + 1 + (4 + 20) + 8; // ~75 bytes per participant
-        let participants_size = 20 * participant_size; // Max 20 participants
-
-        fixed_size + participants_size
-    }
-}
-
-#[account]
-pub struct GlobalState {
-    pub total_groups: u64,             // Total number of groups created
-    pub total_revenue: u64,            // Total fees collected
-    pub active_groups: u64,            // Number of currently active groups
-    pub completed_groups: u64,         // Number of completed groups
-    pub admin: Pubkey,                 // Protocol admin
-    pub fee_percentage: u8,            // Fee percentage (e.g., 1 = 0.1%)
-    pub creator_security_deposit: u64, // Amount in USDC to create a group (6 decimals)
-    pub joiner_security_deposit: u64,  // Amount in USDC to join a group (6 decimals)
-    pub bumps: u8,                     // PDA bump
-}
-
-impl GlobalState {
-    pub const SIZE: usize = 8 +    // discriminator
-                            8 +    // total_groups
-                            8 +    // total_revenue
-                            8 +    // active_groups
-                            8 +    // completed_groups
-                            32 +   // admin
-                            1 +    // fee_percentage
-                            8 +    // creator_security_deposit
-                            8 +    // joiner_security_deposit
-                            1; // bumps
-}
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AjoParticipant {
+    pub pubkey: Pubkey,
+    pub claim_round: u8,
+    pub contribution_round: u8,
+    pub bump: u8,
+    pub security_deposit: u64,  // Deposit paid into the vault when joining
+    pub refunded: bool,         // Whether the deposit has been withdrawn
+    pub bid: u64,               // Discount offered for the current pot in auction groups
+    pub credit: u64,            // Share of other members' auction discounts owed back
+    pub slashed: u64,           // Deposit taken to cover missed contributions
+    pub covered: u64,           // Part of the slashed deposit already paid into pots
+    pub defaulted: bool,        // Blocked from payouts until contributions are caught up
+    pub ejected: bool,          // Voted out; the position waits for a replacement
+    pub total_contributed: u64, // Contributions paid into the vault, excluding penalties
+    pub total_received: u64,    // Payouts received from the vault
+}
+
+impl AjoParticipant {
+    pub const UNASSIGNED_ROUND: u8 = u8::MAX; // claim_round of auction members not yet paid
+
+    pub const SIZE: usize = 32 + // pubkey
+                            1 +  // claim_round
+                            1 +  // contribution_round
+                            1 +  // bump
+                            8 +  // security_deposit
+                            1 +  // refunded
+                            8 +  // bid
+                            8 +  // credit
+                            8 +  // slashed
+                            8 +  // covered
+                            1 +  // defaulted
+                            1 +  // ejected
+                            8 +  // total_contributed
+                            8; // total_received
+}
+
+#[account]
+pub struct AjoGroup {
+    // Basic group information
+    pub name: String,                  // Unique name for the group
+    pub creator: Pubkey,               // Member who created the group
+    pub token_mint: Pubkey,            // Mint the group is denominated in
+    pub security_deposit: u64,         // Amount of token_mint the creator deposited
+    pub contribution_amount: u64,      // Amount of token_mint to contribute each round
+    pub contribution_interval: u16,    // Time between rounds when a user should pay (in days)
+    pub payout_interval: u16,          // Time between payouts (in days)
+    pub num_participants: u8,          // Total number of participants needed
+    pub payout_order: PayoutOrder,     // How payout positions are assigned at start
+    pub fixed_order: Vec<Pubkey>,      // Creator-assigned order for PayoutOrder::Fixed
+    pub order_commit_slot: u64,        // Slot the verifiable shuffle was committed at
+    pub payout_seed: Option<[u8; 32]>, // Seed a randomized order was shuffled with
+    pub late_policy: LatePolicy,       // Grace period and penalty for late contributions
+    pub round_bonus: u64,              // Late penalties added to the next payout
+    pub penalty_pool: u64,             // Late penalties shared out when deposits are refunded
+    pub join_deadline: Option<i64>,    // Group can be expired if it has not filled by then
+
+    // Participants and round management
+    pub participants: Vec<AjoParticipant>, // List of all participants (ordered by join time)
+    pub status: GroupStatus,               // Where the group is in its lifecycle
+    pub start_timestamp: Option<i64>,      // Moved back by however long the group was paused
+    pub paused_at: Option<i64>,            // When the group itself was last paused
+    pub protocol_paused_seconds: i64,      // Protocol pause time already added to start_timestamp
+    pub start_votes: Vec<Pubkey>,          // Members agreeing to start before the group is full
+    pub payout_round: u8, // state for payouts made, useful in calc current round, index of recipient
+
+    pub close_votes: Vec<Pubkey>, // Track who has voted to close
+
+    pub eject_candidate: Option<Pubkey>, // Defaulter currently being voted on
+    pub eject_votes: Vec<Pubkey>,        // Track who has voted to eject the candidate
+
+    pub cycle: u8,                       // Rotation the group is on, counting from 1
+    pub next_cycle_members: Vec<Pubkey>, // Members staying on once the current cycle completes
+    pub next_cycle_terms: Option<CycleTerms>, // Terms announced for the next cycle, if changed
+
+    pub bumps: u8, // PDA bump
+}
+
+impl AjoGroup {
+    pub const MAX_PARTICIPANTS: usize = 20;
+    pub const MAX_NAME_LEN: usize = 32; // The name is a PDA seed, which caps it at 32 bytes
+    pub const REVEAL_DELAY_SLOTS: u64 = 4; // Slots between committing and revealing a shuffle
+    pub const EJECT_OVERDUE_ROUNDS: u8 = 2; // Overdue rounds before a defaulter can be voted out
+
+    // Calculate space required for account
+    pub fn calculate_size(name: &str) -> usize {
+        // Space for fixed fields
+        let fixed_size = 8 +  // account discriminator
+                         (4 + name.len()) +  // name (string)
+                         32 + // creator (Pubkey)
+                         32 + // token_mint (Pubkey)
+                         8 +  // security_deposit (u64)
+                         8 +  // contribution_amount (u64)
+                         2 +  // contribution_interval (u16)
+                         2 +  // payout_interval (u16)
+                         1 +  // num_participants (u8)
+                         1 +  // payout_order (enum)
+                         4 +  // fixed_order vector length
+                         8 +  // order_commit_slot (u64)
+                         (1 + 32) + // payout_seed (Option<[u8; 32]>)
+                         LatePolicy::SIZE + // late_policy
+                         8 +  // round_bonus (u64)
+                         8 +  // penalty_pool (u64)
+                         (1 + 8) + // join_deadline (Option<i64>)
+                         4 +  // participants vector length
+                         1 +  // status (enum)
+                         (1 + 8) + // start_timestamp (Option<i64>)
+                         (1 + 8) + // paused_at (Option<i64>)
+                         8 +  // protocol_paused_seconds (i64)
+                         4 +  // start_votes vector length
+                         1 +  // payout_round (u8)
+                         4 +  // close_votes vector length
+                         (1 + 32) + // eject_candidate (Option<Pubkey>)
+                         4 +  // eject_votes vector length
+                         1 +  // cycle (u8)
+                         4 +  // next_cycle_members vector length
+                         (1 + CycleTerms::SIZE) + // next_cycle_terms (Option<CycleTerms>)
+                         1; // bumps (u8)
+
+        // Space for participants, their start, close and eject votes, the fixed payout order
+        // and the members staying on for the next cycle
+        let participants_size = Self::MAX_PARTICIPANTS * AjoParticipant::SIZE;
+        let start_votes_size = Self::MAX_PARTICIPANTS * 32;
+        let close_votes_size = Self::MAX_PARTICIPANTS * 32;
+        let eject_votes_size = Self::MAX_PARTICIPANTS * 32;
+        let fixed_order_size = Self::MAX_PARTICIPANTS * 32;
+        let next_cycle_size = Self::MAX_PARTICIPANTS * 32;
+
+        fixed_size
+            + participants_size
+            + start_votes_size
+            + close_votes_size
+            + eject_votes_size
+            + fixed_order_size
+            + next_cycle_size
+    }
+
+    // What members opting into the next cycle are signing up for
+    pub fn terms_for_next_cycle(&self) -> CycleTerms {
+        self.next_cycle_terms.unwrap_or(CycleTerms {
+            contribution_amount: self.contribution_amount,
+            payout_order: self.payout_order,
+        })
+    }
+}
+
+// Layout groups were written in before deposits, mints and the creator were recorded.
+// Only read by migrate_group_vault, which rewrites the group as an AjoGroup
+#[derive(AnchorDeserialize, Clone)]
+pub struct LegacyAjoParticipant {
+    pub pubkey: Pubkey,
+    pub claim_round: u8,
+    pub contribution_round: u8,
+    pub bump: u8,
+}
+
+#[derive(AnchorDeserialize, Clone)]
+pub struct LegacyAjoGroup {
+    pub name: String,
+    pub security_deposit: u64, // The creator's deposit
+    pub contribution_amount: u64,
+    pub contribution_interval: u16,
+    pub payout_interval: u16,
+    pub num_participants: u8, // One less than the size asked for; started once this many joined
+    pub participants: Vec<LegacyAjoParticipant>, // Creator first, then in join order
+    pub start_timestamp: Option<i64>,
+    pub payout_round: u8,
+    pub close_votes: Vec<Pubkey>,
+    pub is_closed: bool,
+    pub bumps: u8,
+}
+
+impl LegacyAjoGroup {
+    // Joiners' deposits were never recorded, so they are taken to be the current joiner
+    // deposit; members are paid in join order, as the legacy payout did
+    pub fn upgrade(self, token_mint: Pubkey, joiner_security_deposit: u64) -> AjoGroup {
+        let pot_amount = self.contribution_amount * self.participants.len() as u64;
+        let participants = self
+            .participants
+            .iter()
+            .enumerate()
+            .map(|(index, p)| AjoParticipant {
+                pubkey: p.pubkey,
+                claim_round: index as u8,
+                contribution_round: p.contribution_round,
+                bump: p.bump,
+                security_deposit: if index == 0 {
+                    self.security_deposit
+                } else {
+                    joiner_security_deposit
+                },
+                refunded: false,
+                bid: 0,
+                credit: 0,
+                slashed: 0,
+                covered: 0,
+                defaulted: false,
+                ejected: false,
+                total_contributed: self.contribution_amount * p.contribution_round as u64,
+                total_received: if (index as u8) < self.payout_round {
+                    pot_amount
+                } else {
+                    0
+                },
+            })
+            .collect::<Vec<_>>();
+
+        // Every pot has to be funded by a whole number of contribution rounds. Legacy pots
+        // took one contribution from each member, so uneven intervals fall back to that
+        let contribution_interval = if self
+            .payout_interval
+            .is_multiple_of(self.contribution_interval)
+        {
+            self.contribution_interval
+        } else {
+            self.payout_interval
+        };
+
+        let started = self.start_timestamp.is_some();
+        let status = match (self.is_closed, started) {
+            (true, true) => GroupStatus::Settling,
+            (true, false) => GroupStatus::Cancelled,
+            (false, true) if self.payout_round as usize >= participants.len() => {
+                GroupStatus::Completed
+            }
+            (false, true) => GroupStatus::Active,
+            (false, false) => GroupStatus::Recruiting,
+        };
+
+        AjoGroup {
+            name: self.name,
+            creator: participants[0].pubkey,
+            token_mint,
+            security_deposit: self.security_deposit,
+            contribution_amount: self.contribution_amount,
+            contribution_interval,
+            payout_interval: self.payout_interval,
+            num_participants: if started {
+                participants.len() as u8
+            } else {
+                self.num_participants + 1
+            },
+            payout_order: PayoutOrder::JoinOrder,
+            fixed_order: vec![],
+            order_commit_slot: 0,
+            payout_seed: None,
+            late_policy: LatePolicy::NONE,
+            round_bonus: 0,
+            penalty_pool: 0,
+            join_deadline: None,
+            participants,
+            status,
+            start_timestamp: self.start_timestamp,
+            paused_at: None,
+            protocol_paused_seconds: 0,
+            start_votes: vec![],
+            payout_round: self.payout_round,
+            close_votes: self.close_votes,
+            eject_candidate: None,
+            eject_votes: vec![],
+            cycle: 1,
+            next_cycle_members: vec![],
+            next_cycle_terms: None,
+            bumps: self.bumps,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GroupStatus {
+    Recruiting, // Waiting for members to join
+    Active,     // Contributions and payouts are running
+    Paused,     // Halted by the admin; can resume or be wound down
+    Completed,  // Every member has received their payout; can roll into a new cycle
+    Cancelled,  // Wound down before it started
+    Settling,   // Wound down after it started; deposits are being refunded
+    Closed,     // Every remaining member has been refunded
+}
+
+impl GroupStatus {
+    pub fn can_transition_to(self, next: GroupStatus) -> bool {
+        use GroupStatus::*;
+        matches!(
+            (self, next),
+            (Recruiting, Active)
+                | (Recruiting, Cancelled)
+                | (Active, Paused)
+                | (Paused, Active)
+                | (Active, Completed)
+                | (Active, Settling)
+                | (Paused, Settling)
+                | (Completed, Recruiting)
+                | (Completed, Closed)
+                | (Cancelled, Closed)
+                | (Settling, Closed)
+        )
+    }
+
+    // Members can withdraw their deposits once the group has stopped running
+    pub fn is_settled(self) -> bool {
+        matches!(
+            self,
+            GroupStatus::Completed
+                | GroupStatus::Cancelled
+                | GroupStatus::Settling
+                | GroupStatus::Closed
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PayoutOrder {
+    JoinOrder,        // Members are paid in the order they joined
+    Fixed,            // Creator lists the order before the group starts
+    Random,           // Order is shuffled with a slot hash when the group starts
+    Auction,          // Each pot goes to the unpaid member bidding the highest discount
+    VerifiableRandom, // Shuffled with a slot hash committed at start and revealed later
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LatePenalty {
+    None,
+    Flat(u64),      // Fixed amount per late round
+    BpsPerDay(u16), // Basis points of the contribution per day late
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PenaltyDestination {
+    CurrentRecipient, // Paid on top of the next pot
+    Vault,            // Kept in the vault and shared out with the deposit refunds
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CycleTerms {
+    pub contribution_amount: u64,
+    pub payout_order: PayoutOrder,
+}
+
+impl CycleTerms {
+    pub const SIZE: usize = 8 + // contribution_amount
+                            1; // payout_order
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LatePolicy {
+    pub grace_period_hours: u16, // Time after a round is due before it counts as late
+    pub penalty: LatePenalty,
+    pub destination: PenaltyDestination,
+}
+
+impl LatePolicy {
+    pub const SIZE: usize = 2 +     // grace_period_hours
+                            (1 + 8) + // penalty
+                            1; // destination
+
+    pub const NONE: Self = Self {
+        grace_period_hours: 0,
+        penalty: LatePenalty::None,
+        destination: PenaltyDestination::Vault,
+    };
+
+    pub fn is_valid(&self) -> bool {
+        match self.penalty {
+            LatePenalty::BpsPerDay(bps) => bps > 0 && bps <= 10_000,
+            LatePenalty::Flat(amount) => amount > 0,
+            LatePenalty::None => true,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FeeMode {
+    OnPayout,       // Fee is deducted from the pot before it is paid out
+    OnContribution, // Fee is charged on top of every contribution
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GroupLimits {
+    pub min_participants: u8,
+    pub max_participants: u8,
+    pub min_contribution_interval: u16, // In days
+    pub max_contribution_interval: u16, // In days
+    pub min_payout_interval: u16,       // In days
+    pub max_payout_interval: u16,       // In days
+}
+
+impl GroupLimits {
+    pub const SIZE: usize = 1 + // min_participants
+                            1 + // max_participants
+                            2 + // min_contribution_interval
+                            2 + // max_contribution_interval
+                            2 + // min_payout_interval
+                            2; // max_payout_interval
+
+    pub const DEFAULT: Self = Self {
+        min_participants: 3,
+        max_participants: AjoGroup::MAX_PARTICIPANTS as u8,
+        min_contribution_interval: 1,
+        max_contribution_interval: 90,
+        min_payout_interval: 7,
+        max_payout_interval: 90,
+    };
+
+    pub fn is_valid(&self) -> bool {
+        self.min_participants >= 2
+            && self.min_participants <= self.max_participants
+            && self.max_participants as usize <= AjoGroup::MAX_PARTICIPANTS
+            && self.min_contribution_interval > 0
+            && self.min_contribution_interval <= self.max_contribution_interval
+            && self.min_payout_interval > 0
+            && self.min_payout_interval <= self.max_payout_interval
+    }
+}
+
+// Fields left as None keep their current value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct GlobalConfigParams {
+    pub fee_percentage: Option<u8>,
+    pub fee_mode: Option<FeeMode>,
+    pub creator_security_deposit: Option<u64>,
+    pub joiner_security_deposit: Option<u64>,
+    pub limits: Option<GroupLimits>,
+}
+
+#[account]
+pub struct GlobalState {
+    pub total_groups: u64,                 // Total number of groups created
+    pub active_groups: u64,                // Number of currently active groups
+    pub completed_groups: u64,             // Number of completed groups
+    pub expired_groups: u64, // Number of groups that never filled before their deadline
+    pub admin: Pubkey,       // Protocol admin
+    pub fee_percentage: u8,  // Fee percentage (e.g., 1 = 0.1%)
+    pub fee_mode: FeeMode,   // Whether the fee is taken on payout or on contribution
+    pub creator_security_deposit: u64, // Amount to create a group (DEPOSIT_DECIMALS)
+    pub joiner_security_deposit: u64, // Amount to join a group (DEPOSIT_DECIMALS)
+    pub limits: GroupLimits, // Bounds enforced when creating a group
+    pub pending_admin: Option<Pubkey>, // Admin proposed but not yet accepted
+    pub treasury_multisig: Option<Pubkey>, // Extra key allowed to withdraw treasury fees
+    pub paused: bool,        // Emergency stop for group activity
+    pub paused_at: Option<i64>, // When the current pause began
+    pub paused_seconds: i64, // Time spent in pauses that have ended
+    pub bumps: u8,           // PDA bump
+}
+
+impl GlobalState {
+    // Decimals the configured security deposits are expressed in,
+    // scaled to each group's mint when a deposit is taken
+    pub const DEPOSIT_DECIMALS: u8 = 6;
+
+    // Seconds the protocol has spent paused up to `now`
+    pub fn paused_seconds_at(&self, now: i64) -> i64 {
+        self.paused_seconds + self.paused_at.map_or(0, |paused_at| now - paused_at)
+    }
+
+    pub const SIZE: usize = 8 +    // discriminator
+                            8 +    // total_groups
+                            8 +    // active_groups
+                            8 +    // completed_groups
+                            8 +    // expired_groups
+                            32 +   // admin
+                            1 +    // fee_percentage
+                            1 +    // fee_mode
+                            8 +    // creator_security_deposit
+                            8 +    // joiner_security_deposit
+                            GroupLimits::SIZE + // limits
+                            (1 + 32) + // pending_admin
+                            (1 + 32) + // treasury_multisig
+                            1 +    // paused
+                            (1 + 8) + // paused_at
+                            8 +    // paused_seconds
+                            1; // bumps
+}
+
+// Layout the first release wrote the global state in. Only read by migrate_global_state,
+// which rewrites it as a GlobalState
+#[derive(AnchorDeserialize, Clone)]
+pub struct LegacyGlobalState {
+    pub total_groups: u64,
+    pub total_revenue: u64, // A counter only; fees are now accounted per mint in TreasuryState
+    pub active_groups: u64,
+    pub completed_groups: u64,
+    pub admin: Pubkey,
+    pub fee_percentage: u8,
+    pub creator_security_deposit: u64,
+    pub joiner_security_deposit: u64,
+    pub bumps: u8,
+}
+
+impl LegacyGlobalState {
+    // Settings the first release did not have start out as `initialize` sets them
+    pub fn upgrade(self) -> GlobalState {
+        GlobalState {
+            total_groups: self.total_groups,
+            active_groups: self.active_groups,
+            completed_groups: self.completed_groups,
+            expired_groups: 0,
+            admin: self.admin,
+            fee_percentage: self.fee_percentage,
+            fee_mode: FeeMode::OnPayout,
+            creator_security_deposit: self.creator_security_deposit,
+            joiner_security_deposit: self.joiner_security_deposit,
+            limits: GroupLimits::DEFAULT,
+            pending_admin: None,
+            treasury_multisig: None,
+            paused: false,
+            paused_at: None,
+            paused_seconds: 0,
+            bumps: self.bumps,
+        }
+    }
+}
+
+#[account]
+pub struct AllowedMint {
+    pub mint: Pubkey, // Mint groups may be denominated in
+    pub bumps: u8,    // PDA bump
+}
+
+impl AllowedMint {
+    pub const SIZE: usize = 8 +    // discriminator
+                            32 +   // mint
+                            1; // bumps
+}
+
+// Fee accounting for a single mint's treasury; amounts are in that mint's units
+#[account]
+pub struct TreasuryState {
+    pub mint: Pubkey,         // Mint the treasury holds
+    pub total_revenue: u64,   // Fees collected in this mint
+    pub total_withdrawn: u64, // Fees withdrawn from this mint's treasury
+    pub bumps: u8,            // PDA bump
+}
+
+impl TreasuryState {
+    pub const SIZE: usize = 8 +    // discriminator
+                            32 +   // mint
+                            8 +    // total_revenue
+                            8 +    // total_withdrawn
+                            1; // bumps
+
+    // Fees accrued that have not yet been withdrawn
+    pub fn available_revenue(&self) -> u64 {
+        self.total_revenue - self.total_withdrawn
+    }
+}
+
+#[account]
+pub struct SwapProposal {
+    pub ajo_group: Pubkey,            // Group the positions belong to
+    pub proposer: Pubkey,             // Member offering the trade
+    pub counterparty: Pubkey,         // Member who has to accept it
+    pub proposer_claim_round: u8,     // Proposer's position when the offer was made
+    pub counterparty_claim_round: u8, // Counterparty's position when the offer was made
+    pub sweetener: u64,               // Escrowed tokens paid to the counterparty on accept
+    pub bumps: u8,                    // PDA bump
+}
+
+impl SwapProposal {
+    pub const SIZE: usize = 8 +    // discriminator
+                            32 +   // ajo_group
+                            32 +   // proposer
+                            32 +   // counterparty
+                            1 +    // proposer_claim_round
+                            1 +    // counterparty_claim_round
+                            8 +    // sweetener
+                            1; // bumps
+}
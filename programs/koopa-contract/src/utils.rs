@@ -1,62 +1,327 @@
-use crate::state::*;
-use anchor_lang::prelude::*;
-
-// Helper function to find the PDA for an Ajo group
-pub fn find_group_pda(name: &str, program_id: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[b"ajo-group", name.as_bytes()], program_id)
-}
-
-// Convert days to seconds
-pub fn days_to_seconds(days: u16) -> i64 {
-    (days as i64) * 24 * 60 * 60
-}
-
-// Calculate fee amount based on contribution
-pub fn calculate_fee(amount: u64, fee_percentage: u8) -> u64 {
-    // Fee is calculated as (amount * fee_percentage) / 1000
-    // This allows for fractional percentages (e.g., 1 = 0.1%)
-    (amount * fee_percentage as u64) / 1000
-}
-
-// Check if a participant should contribute in the current round
-pub fn should_contribute(group: &AjoGroup, participant_pubkey: &Pubkey) -> bool {
-    // Get the current recipient's pubkey
-    let current_recipient = group
-        .participants
-        .iter()
-        .find(|p| p.claim_round == group.payout_round)
-        .map(|p| p.pubkey);
-
-    // If participant is the current recipient, they don't need to contribute
-    if let Some(recipient) = current_recipient {
-        if recipient == *participant_pubkey {
-            return false;
-        }
-    }
-
-    // Otherwise, they should contribute
-    true
-}
-
-// Calculate the total group contribution per round
-pub fn calculate_round_total(group: &AjoGroup) -> u64 {
-    // Total contribution is the contribution amount times the number of contributors
-    // (which is all participants except the current recipient)
-    group.contribution_amount * (group.participants.len() as u64 - 1)
-}
-
-// Check if all participants have contributed for the current round
-pub fn all_contributed(group: &AjoGroup) -> bool {
-    let current_round = group.payout_round;
-
-    // Get the current recipient
-    let recipient_index = (current_round as usize) % group.participants.len();
-    let current_recipient = group.participants[recipient_index].pubkey;
-
-    // Check if all other participants have contributed to this round
-    group
-        .participants
-        .iter()
-        .filter(|p| p.pubkey != current_recipient)
-        .all(|p| p.contribution_round >= current_round)
-}
+use crate::errors::*;
+use crate::schedule::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+};
+use std::cmp::Reverse;
+
+// Helper function to find the PDA for an Ajo group
+pub fn find_group_pda(name: &str, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"ajo-group", name.as_bytes()], program_id)
+}
+
+// Group name as a PDA seed, rejected before address derivation would fail on it
+pub fn group_name_seed(name: &str) -> Result<&[u8]> {
+    require!(
+        name.len() <= AjoGroup::MAX_NAME_LEN,
+        KooPaaError::NameTooLong
+    );
+    Ok(name.as_bytes())
+}
+
+// Helper function to find the PDA for an Ajo group's token vault
+pub fn find_group_vault_pda(group: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"group-vault", group.as_ref()], program_id)
+}
+
+// Convert days to seconds
+pub fn days_to_seconds(days: u16) -> i64 {
+    (days as i64) * 24 * 60 * 60
+}
+
+// Scale an amount expressed in GlobalState::DEPOSIT_DECIMALS to a mint's decimals
+pub fn scale_to_decimals(amount: u64, decimals: u8) -> Result<u64> {
+    if decimals >= GlobalState::DEPOSIT_DECIMALS {
+        10u64
+            .checked_pow((decimals - GlobalState::DEPOSIT_DECIMALS) as u32)
+            .and_then(|scale| amount.checked_mul(scale))
+            .ok_or(error!(KooPaaError::DepositOverflow))
+    } else {
+        Ok(amount / 10u64.pow((GlobalState::DEPOSIT_DECIMALS - decimals) as u32))
+    }
+}
+
+// Amount that has to be sent so `amount` arrives after any Token-2022 transfer fee
+pub fn gross_up_for_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    // Legacy SPL Token mints never charge transfer fees
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    let transfer_fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(KooPaaError::TransferFeeShortfall)?,
+        Err(_) => 0,
+    };
+
+    Ok(amount + transfer_fee)
+}
+
+// Fail unless the group is running, with the error that explains why it is not
+pub fn require_active(group: &AjoGroup) -> Result<()> {
+    match group.status {
+        GroupStatus::Active => Ok(()),
+        GroupStatus::Recruiting => err!(KooPaaError::GroupNotStarted),
+        GroupStatus::Paused => err!(KooPaaError::GroupPaused),
+        GroupStatus::Completed => err!(KooPaaError::GroupCompleted),
+        _ => err!(KooPaaError::GroupAlreadyClosed),
+    }
+}
+
+// Fail unless the group is still taking members
+pub fn require_recruiting(group: &AjoGroup) -> Result<()> {
+    match group.status {
+        GroupStatus::Recruiting => Ok(()),
+        GroupStatus::Active | GroupStatus::Paused | GroupStatus::Completed => {
+            err!(KooPaaError::GroupAlreadyStarted)
+        }
+        _ => err!(KooPaaError::GroupAlreadyClosed),
+    }
+}
+
+// Calculate fee amount based on contribution
+pub fn calculate_fee(amount: u64, fee_percentage: u8) -> u64 {
+    // Fee is calculated as (amount * fee_percentage) / 1000
+    // This allows for fractional percentages (e.g., 1 = 0.1%)
+    (amount * fee_percentage as u64) / 1000
+}
+
+// Largest discount an auction bid can offer on `pot_amount`: the pot after any payout
+// fee, less the winner's own contribution
+pub fn max_payout_discount(group: &AjoGroup, global_state: &GlobalState, pot_amount: u64) -> u64 {
+    let own_share = contribution_per_payout(group);
+    let fee_amount = if global_state.fee_mode == FeeMode::OnPayout {
+        calculate_fee(pot_amount, global_state.fee_percentage)
+    } else {
+        0
+    };
+    pot_amount.saturating_sub(fee_amount + own_share)
+}
+
+// Contributions a participant has made beyond the rounds already paid out
+pub fn calculate_unspent_contributions(group: &AjoGroup, participant: &AjoParticipant) -> u64 {
+    // Every payout consumes one pot's worth of rounds from each participant
+    let spent_rounds = group.payout_round * rounds_per_payout(group);
+    let unspent_rounds = participant.contribution_round.saturating_sub(spent_rounds);
+    group.contribution_amount * unspent_rounds as u64
+}
+
+// Late contributions for rounds `first_round..=last_round` whose pot was already paid out.
+// Whatever the member's slashed deposit put into the pot in their place is won back for
+// the deposit, the rest is owed to that pot's recipient. Returns the amount won back
+pub fn settle_late_rounds(
+    group: &mut AjoGroup,
+    index: usize,
+    first_round: u8,
+    last_round: u8,
+) -> u64 {
+    let paid_out_rounds = group.payout_round * rounds_per_payout(group);
+    let mut total_restored = 0;
+
+    for round in first_round..=last_round.min(paid_out_rounds) {
+        let participant = &mut group.participants[index];
+        let restored = participant.covered.min(group.contribution_amount);
+        participant.covered -= restored;
+        total_restored += restored;
+        let owed = group.contribution_amount - restored;
+
+        // A recipient voted out since has no credit left to claim
+        let pot = (round - 1) / rounds_per_payout(group);
+        match group
+            .participants
+            .iter_mut()
+            .find(|p| p.claim_round == pot && !p.ejected)
+        {
+            Some(recipient) => recipient.credit += owed,
+            None => group.penalty_pool += owed,
+        }
+    }
+
+    total_restored
+}
+
+// Contributions held for rounds that have not opened yet; refunded with the deposit
+// if the group is wound down before they are paid out
+pub fn prepaid_balance(group: &AjoGroup, participant: &AjoParticipant, now: i64) -> u64 {
+    group.contribution_amount * prepaid_rounds(group, participant, now) as u64
+}
+
+// Index of the participant whose claim_round is up for the current payout
+pub fn current_recipient_index(group: &AjoGroup) -> Option<usize> {
+    // Auction positions are only decided when each pot is paid out
+    if group.payout_order == PayoutOrder::Auction {
+        return auction_winner_index(group);
+    }
+
+    let round = (group.payout_round as usize % group.participants.len()) as u8;
+    group
+        .participants
+        .iter()
+        .position(|p| p.claim_round == round)
+}
+
+// Give every participant a claim_round according to the group's payout order
+pub fn assign_claim_rounds(group: &mut AjoGroup, seed: &[u8; 32]) {
+    let mut order: Vec<usize> = (0..group.participants.len()).collect();
+
+    match group.payout_order {
+        PayoutOrder::JoinOrder => {}
+        PayoutOrder::Fixed => {
+            // Listed members go first in the creator's order, the rest keep join order
+            order.sort_by_key(|&index| {
+                group
+                    .fixed_order
+                    .iter()
+                    .position(|key| *key == group.participants[index].pubkey)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        PayoutOrder::Random | PayoutOrder::VerifiableRandom => shuffle(&mut order, seed),
+        PayoutOrder::Auction => {
+            // Every position is auctioned off round by round
+            for participant in group.participants.iter_mut() {
+                participant.claim_round = AjoParticipant::UNASSIGNED_ROUND;
+            }
+            return;
+        }
+    }
+
+    for (claim_round, index) in order.into_iter().enumerate() {
+        group.participants[index].claim_round = claim_round as u8;
+    }
+}
+
+// Unpaid participant bidding the highest discount, ties go to the earliest joiner
+pub fn auction_winner_index(group: &AjoGroup) -> Option<usize> {
+    group
+        .participants
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.claim_round == AjoParticipant::UNASSIGNED_ROUND && !p.defaulted)
+        .max_by_key(|(index, p)| (p.bid, Reverse(*index)))
+        .map(|(index, _)| index)
+}
+
+// Fisher-Yates shuffle where each swap is drawn from a hash of the seed.
+// Starting from join order, anyone holding the group's payout_seed can replay it
+pub fn shuffle(order: &mut [usize], seed: &[u8; 32]) {
+    for i in (1..order.len()).rev() {
+        let hash = hashv(&[seed, &(i as u64).to_le_bytes()]).to_bytes();
+        let draw = u64::from_le_bytes(hash[..8].try_into().unwrap());
+        order.swap(i, (draw % (i as u64 + 1)) as usize);
+    }
+}
+
+// Participants' pubkeys in the order they will be paid; auction positions only appear once won
+pub fn payout_schedule(group: &AjoGroup) -> Vec<Pubkey> {
+    let mut participants: Vec<&AjoParticipant> = group
+        .participants
+        .iter()
+        .filter(|p| p.claim_round != AjoParticipant::UNASSIGNED_ROUND)
+        .collect();
+    participants.sort_by_key(|p| p.claim_round);
+    participants.iter().map(|p| p.pubkey).collect()
+}
+
+// Most recent hash in the SlotHashes sysvar, read without deserializing every entry
+pub fn latest_slot_hash(slot_hashes: &AccountInfo) -> Result<[u8; 32]> {
+    let data = slot_hashes.try_borrow_data()?;
+
+    // Layout is a u64 entry count followed by (slot: u64, hash: [u8; 32]) entries, newest first
+    require!(data.len() >= 48, KooPaaError::SlotHashUnavailable);
+    Ok(data[16..48].try_into().unwrap())
+}
+
+// Whether every participant has a final claim_round to be paid against
+pub fn payout_order_revealed(group: &AjoGroup) -> bool {
+    group.payout_order != PayoutOrder::VerifiableRandom || group.payout_seed.is_some()
+}
+
+// State of the slot hash a verifiable shuffle was committed to
+pub enum RevealHash {
+    Pending,         // The reveal slot has not been reached yet
+    Expired,         // The reveal slot fell out of the sysvar and has to be re-committed
+    Ready([u8; 32]), // Hash of the first slot at or after the reveal slot
+}
+
+// Look up the first slot hash at or after `reveal_slot` in the SlotHashes sysvar
+pub fn find_reveal_hash(slot_hashes: &AccountInfo, reveal_slot: u64) -> Result<RevealHash> {
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8, KooPaaError::SlotHashUnavailable);
+
+    // Entries are (slot: u64, hash: [u8; 32]) pairs ordered newest first
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+    let mut revealed = None;
+    let mut oldest_slot = None;
+
+    for entry in data[8..].chunks_exact(40).take(count) {
+        let slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+        if slot >= reveal_slot {
+            revealed = Some(entry[8..].try_into().unwrap());
+        }
+        oldest_slot = Some(slot);
+    }
+
+    // Skipped slots never get a hash, so the first one after the reveal slot is only
+    // final while the sysvar still reaches back to the reveal slot itself
+    Ok(match (revealed, oldest_slot) {
+        (Some(hash), Some(oldest)) if oldest <= reveal_slot => RevealHash::Ready(hash),
+        (Some(_), _) => RevealHash::Expired,
+        _ => RevealHash::Pending,
+    })
+}
+
+// Seed for a verifiable shuffle, unique per group even when revealed in the same slot
+pub fn reveal_seed(slot_hash: &[u8; 32], group: &Pubkey) -> [u8; 32] {
+    hashv(&[slot_hash, group.as_ref()]).to_bytes()
+}
+
+// A participant's position, as long as it has not been paid out yet
+pub fn unpaid_claim_round(group: &AjoGroup, participant: &Pubkey) -> Result<u8> {
+    let participant = group
+        .participants
+        .iter()
+        .find(|p| p.pubkey == *participant)
+        .ok_or(KooPaaError::NotParticipant)?;
+
+    require!(
+        participant.claim_round >= group.payout_round,
+        KooPaaError::PositionAlreadyPaid
+    );
+    Ok(participant.claim_round)
+}
+
+// Penalty for paying `round` at `now`, and how many days late it is; rounds paid
+// within the grace period, or in groups without a penalty, are not late at all
+pub fn calculate_late_penalty(group: &AjoGroup, round: u8, now: i64) -> (u64, u64) {
+    let window = contribution_window(group, round);
+
+    if group.late_policy.penalty == LatePenalty::None || now <= window.late_after {
+        return (0, 0);
+    }
+
+    // Partial days count as a full day late
+    let days_late = ((now - window.due_at + days_to_seconds(1) - 1) / days_to_seconds(1)) as u64;
+    let penalty = match group.late_policy.penalty {
+        LatePenalty::None => 0,
+        LatePenalty::Flat(amount) => amount,
+        LatePenalty::BpsPerDay(bps) => group.contribution_amount * bps as u64 * days_late / 10_000,
+    };
+
+    (penalty, days_late)
+}
+
+// Rounds a participant still owes whose grace period has already run out
+pub fn overdue_rounds(group: &AjoGroup, participant: &AjoParticipant, now: i64) -> u8 {
+    (participant.contribution_round + 1..=total_contribution_rounds(group))
+        .take_while(|&round| now > contribution_window(group, round).late_after)
+        .count() as u8
+}
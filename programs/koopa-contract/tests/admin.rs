@@ -0,0 +1,443 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use koopa_contract::{errors::KooPaaError, state::*};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn initialize_sets_defaults() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let global_state = env.global_state().await;
+
+    assert_eq!(global_state.admin, env.admin.pubkey());
+    assert_eq!(global_state.fee_percentage, 10);
+    assert_eq!(global_state.fee_mode, FeeMode::OnPayout);
+    assert_eq!(global_state.creator_security_deposit, 5 * USDC);
+    assert_eq!(global_state.joiner_security_deposit, 2 * USDC);
+    assert_eq!(global_state.limits, GroupLimits::DEFAULT);
+    assert!(!global_state.paused);
+
+    let allowed_mint: AllowedMint = env.fetch(&allowed_mint_pda(&env.mint)).await;
+    assert_eq!(allowed_mint.mint, env.mint);
+}
+
+#[tokio::test]
+async fn update_global_config_validates_params() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+
+    let ix = env.ix_update_global_config(GlobalConfigParams {
+        fee_percentage: Some(20),
+        fee_mode: Some(FeeMode::OnContribution),
+        creator_security_deposit: Some(7 * USDC),
+        joiner_security_deposit: None,
+        limits: None,
+    });
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    let global_state = env.global_state().await;
+    assert_eq!(global_state.fee_percentage, 20);
+    assert_eq!(global_state.fee_mode, FeeMode::OnContribution);
+    assert_eq!(global_state.creator_security_deposit, 7 * USDC);
+    assert_eq!(global_state.joiner_security_deposit, 2 * USDC);
+
+    let cases = [
+        (
+            GlobalConfigParams {
+                fee_percentage: Some(101),
+                ..Default::default()
+            },
+            KooPaaError::InvalidFeePercentage,
+        ),
+        (
+            GlobalConfigParams {
+                joiner_security_deposit: Some(0),
+                ..Default::default()
+            },
+            KooPaaError::InvalidSecurityDeposit,
+        ),
+        (
+            GlobalConfigParams {
+                limits: Some(GroupLimits {
+                    min_participants: 10,
+                    max_participants: 5,
+                    ..GroupLimits::DEFAULT
+                }),
+                ..Default::default()
+            },
+            KooPaaError::InvalidGroupLimits,
+        ),
+    ];
+    for (params, expected) in cases {
+        let ix = env.ix_update_global_config(params);
+        assert_error(env.process(&[ix], &[&admin]).await, expected);
+    }
+}
+
+#[tokio::test]
+async fn admin_instructions_reject_other_signers() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let intruder = Keypair::new();
+    env.airdrop(&intruder.pubkey(), 1_000_000_000).await;
+
+    let admin = env.admin.pubkey();
+    let instructions = [
+        env.ix_update_global_config(GlobalConfigParams::default()),
+        env.ix_propose_admin(&intruder.pubkey()),
+        env.ix_pause(),
+        env.ix_set_treasury_multisig(None),
+        env.ix_remove_allowed_mint(&env.mint),
+    ];
+    for mut ix in instructions {
+        replace_account(&mut ix, &admin, &intruder.pubkey());
+        assert_error(
+            env.process(&[ix], &[&intruder]).await,
+            KooPaaError::OnlyAdminCanUpdate,
+        );
+    }
+}
+
+#[tokio::test]
+async fn admin_transfer_requires_acceptance() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    let new_admin = Keypair::new();
+    let stranger = Keypair::new();
+
+    // Nobody can accept before a proposal exists
+    let ix = env.ix_accept_admin(&new_admin.pubkey());
+    assert_error(
+        env.process(&[ix], &[&new_admin]).await,
+        KooPaaError::NotPendingAdmin,
+    );
+
+    let ix = env.ix_propose_admin(&new_admin.pubkey());
+    env.process(&[ix], &[&admin]).await.unwrap();
+    assert_eq!(
+        env.global_state().await.pending_admin,
+        Some(new_admin.pubkey())
+    );
+
+    let ix = env.ix_accept_admin(&stranger.pubkey());
+    assert_error(
+        env.process(&[ix], &[&stranger]).await,
+        KooPaaError::NotPendingAdmin,
+    );
+
+    let ix = env.ix_accept_admin(&new_admin.pubkey());
+    env.process(&[ix], &[&new_admin]).await.unwrap();
+
+    let global_state = env.global_state().await;
+    assert_eq!(global_state.admin, new_admin.pubkey());
+    assert_eq!(global_state.pending_admin, None);
+}
+
+#[tokio::test]
+async fn pause_blocks_group_activity_until_unpaused() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    let members = env.setup_group("paused", 100 * USDC, 7, 2).await;
+    let creator = env.create_user(1_000 * USDC).await;
+
+    let ix = env.ix_pause();
+    env.process(&[ix], &[&admin]).await.unwrap();
+    assert!(env.global_state().await.paused);
+
+    let ix = env.ix_create_group(&creator, "blocked", 100 * USDC, 7, 7, 3);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::ProtocolPaused,
+    );
+
+    let ix = env.ix_contribute(&members[0], "paused");
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::ProtocolPaused,
+    );
+
+    let ix = env.ix_unpause();
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    let ix = env.ix_contribute(&members[0], "paused");
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn protocol_pause_does_not_count_against_members() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    let name = "stalled";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (recipient, late) = (&members[0], &members[2]);
+
+    for member in &members[..2] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // Ten of the thirteen days that pass are spent paused, so round one is not due yet
+    env.warp_days(3).await;
+    let ix = env.ix_pause();
+    env.process(&[ix], &[&admin]).await.unwrap();
+    env.warp_days(10).await;
+    let ix = env.ix_unpause();
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    let ix = env.ix_slash_defaulter(recipient, name, &late.pubkey());
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::NotInDefault,
+    );
+    let ix = env.ix_payout(recipient, name);
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::PayoutNotYetDue,
+    );
+
+    let ix = env.ix_contribute(late, name);
+    env.process(&[ix], &[&late.keypair]).await.unwrap();
+    assert_eq!(env.group(name).await.participants[2].contribution_round, 1);
+
+    env.warp_days(4).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn mint_too_precise_for_the_deposits_is_not_allowed() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+
+    // 5 USDC at 20 decimals is more than a u64 can hold
+    let mint = env.create_mint(20).await;
+    let ix = env.ix_add_allowed_mint(&mint);
+    assert_error(
+        env.process(&[ix], &[&admin]).await,
+        KooPaaError::DepositOverflow,
+    );
+
+    let mint = env.create_mint(18).await;
+    let ix = env.ix_add_allowed_mint(&mint);
+    env.process(&[ix], &[&admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn removed_mint_cannot_back_new_groups() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    let creator = env.create_user(1_000 * USDC).await;
+
+    let ix = env.ix_remove_allowed_mint(&env.mint);
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    let ix = env.ix_create_group(&creator, "delisted", 100 * USDC, 7, 7, 3);
+    assert!(env.process(&[ix], &[&creator.keypair]).await.is_err());
+}
+
+#[tokio::test]
+async fn treasury_withdrawal_is_bounded_and_gated() {
+    let mut env = TestEnv::new(10, FeeMode::OnContribution).await;
+    let admin = env.admin.insecure_clone();
+    let members = env.setup_group("treasury", 100 * USDC, 7, 2).await;
+
+    for member in &members {
+        let ix = env.ix_contribute(member, "treasury");
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    let mint = env.mint;
+    assert_eq!(env.treasury_state(&mint).await.total_revenue, 3 * USDC);
+
+    let multisig = Keypair::new();
+    let admin_account = env.create_token_account(&admin.pubkey(), &mint).await;
+    let multisig_account = env.create_token_account(&multisig.pubkey(), &mint).await;
+
+    let ix = env.ix_withdraw_treasury(&multisig.pubkey(), &multisig_account, USDC);
+    assert_error(
+        env.process(&[ix], &[&multisig]).await,
+        KooPaaError::UnauthorizedTreasuryWithdrawal,
+    );
+
+    let ix = env.ix_withdraw_treasury(&admin.pubkey(), &admin_account, 4 * USDC);
+    assert_error(
+        env.process(&[ix], &[&admin]).await,
+        KooPaaError::WithdrawalExceedsRevenue,
+    );
+
+    let ix = env.ix_withdraw_treasury(&admin.pubkey(), &admin_account, USDC);
+    env.process(&[ix], &[&admin]).await.unwrap();
+    assert_eq!(env.token_balance(&admin_account).await, USDC);
+
+    // Once a multisig is set it may withdraw as well
+    let ix = env.ix_set_treasury_multisig(Some(multisig.pubkey()));
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    let ix = env.ix_withdraw_treasury(&multisig.pubkey(), &multisig_account, 2 * USDC);
+    env.process(&[ix], &[&multisig]).await.unwrap();
+    assert_eq!(env.token_balance(&multisig_account).await, 2 * USDC);
+
+    let treasury_state = env.treasury_state(&mint).await;
+    assert_eq!(treasury_state.total_withdrawn, 3 * USDC);
+    assert_eq!(treasury_state.available_revenue(), 0);
+    assert_eq!(env.token_balance(&treasury_pda(&env.mint)).await, 0);
+}
+
+#[tokio::test]
+async fn treasury_withdrawal_is_bounded_per_mint() {
+    let mut env = TestEnv::new(10, FeeMode::OnContribution).await;
+    let admin = env.admin.insecure_clone();
+    let members = env.setup_group("earning", 100 * USDC, 7, 2).await;
+    for member in &members {
+        let ix = env.ix_contribute(member, "earning");
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    let other_mint = env.create_mint(DECIMALS).await;
+    let add_mint = env.ix_add_allowed_mint(&other_mint);
+    let treasury = env.ix_initialize_treasury(&other_mint);
+    env.process(&[add_mint, treasury], &[&admin]).await.unwrap();
+
+    // Tokens sent straight to a treasury are not revenue, and fees in another mint
+    // never raise its limit
+    env.mint_to(&other_mint, &treasury_pda(&other_mint), 3 * USDC)
+        .await;
+    let destination = env.create_token_account(&admin.pubkey(), &other_mint).await;
+    let ix = env.ix_withdraw_treasury_in(&other_mint, &admin.pubkey(), &destination, USDC);
+    assert_error(
+        env.process(&[ix], &[&admin]).await,
+        KooPaaError::WithdrawalExceedsRevenue,
+    );
+
+    let treasury_state = env.treasury_state(&other_mint).await;
+    assert_eq!(treasury_state.mint, other_mint);
+    assert_eq!(treasury_state.total_revenue, 0);
+    let mint = env.mint;
+    assert_eq!(env.treasury_state(&mint).await.total_revenue, 3 * USDC);
+}
+
+#[tokio::test]
+async fn pausing_a_group_halts_it_until_resumed() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    env.setup_partial_group("recruiting", 100 * USDC, 7, 3, 1)
+        .await;
+    let members = env.setup_group("halted", 100 * USDC, 7, 2).await;
+
+    // Only a running group can be paused
+    let ix = env.ix_pause_group("recruiting");
+    assert_error(
+        env.process(&[ix], &[&admin]).await,
+        KooPaaError::InvalidStatusTransition,
+    );
+
+    let ix = env.ix_pause_group("halted");
+    env.process(&[ix], &[&admin]).await.unwrap();
+    assert_eq!(env.group("halted").await.status, GroupStatus::Paused);
+
+    let ix = env.ix_contribute(&members[0], "halted");
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::GroupPaused,
+    );
+
+    let ix = env.ix_resume_group("halted");
+    env.process(&[ix], &[&admin]).await.unwrap();
+    let ix = env.ix_resume_group("halted");
+    assert_error(
+        env.process(&[ix], &[&admin]).await,
+        KooPaaError::InvalidStatusTransition,
+    );
+
+    let ix = env.ix_contribute(&members[0], "halted");
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    // A paused group can still be wound down, after which nobody can contribute
+    let ix = env.ix_pause_group("halted");
+    env.process(&[ix], &[&admin]).await.unwrap();
+    for member in &members[..2] {
+        let ix = env.ix_close_group(member, "halted");
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    assert_eq!(env.group("halted").await.status, GroupStatus::Settling);
+
+    let ix = env.ix_contribute(&members[1], "halted");
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::GroupAlreadyClosed,
+    );
+}
+
+#[tokio::test]
+async fn group_pause_does_not_count_against_members() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    let name = "halted";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (recipient, late) = (&members[0], &members[2]);
+
+    for member in &members[..2] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    env.warp_days(3).await;
+    let ix = env.ix_pause_group(name);
+    env.process(&[ix], &[&admin]).await.unwrap();
+    env.warp_days(10).await;
+    let ix = env.ix_resume_group(name);
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    // Round one now falls due ten days later than it would have
+    let ix = env.ix_slash_defaulter(recipient, name, &late.pubkey());
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::NotInDefault,
+    );
+    let ix = env.ix_payout(recipient, name);
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::PayoutNotYetDue,
+    );
+
+    env.warp_days(4).await;
+    env.warp_seconds(1).await;
+    let ix = env.ix_slash_defaulter(recipient, name, &late.pubkey());
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn legacy_global_state_is_upgraded_in_place() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let admin = env.admin.insecure_clone();
+    let intruder = Keypair::new();
+    env.airdrop(&intruder.pubkey(), 1_000_000_000).await;
+    env.install_legacy_global_state(4).await;
+
+    let ix = env.ix_migrate_global_state(&intruder.pubkey());
+    assert_error(
+        env.process(&[ix], &[&intruder]).await,
+        KooPaaError::OnlyAdminCanUpdate,
+    );
+
+    let ix = env.ix_migrate_global_state(&admin.pubkey());
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    // Counters and settings carry over, everything the first release lacked is defaulted
+    let global_state = env.global_state().await;
+    assert_eq!(global_state.total_groups, 4);
+    assert_eq!(global_state.active_groups, 1);
+    assert_eq!(global_state.admin, admin.pubkey());
+    assert_eq!(global_state.fee_percentage, 10);
+    assert_eq!(global_state.fee_mode, FeeMode::OnPayout);
+    assert_eq!(global_state.creator_security_deposit, 5 * USDC);
+    assert_eq!(global_state.joiner_security_deposit, 2 * USDC);
+    assert_eq!(global_state.limits, GroupLimits::DEFAULT);
+    assert!(!global_state.paused);
+
+    // An account already in the current layout is left alone
+    let ix = env.ix_migrate_global_state(&admin.pubkey());
+    assert_error(
+        env.process(&[ix], &[&admin]).await,
+        ErrorCode::AccountDidNotDeserialize,
+    );
+}
@@ -0,0 +1,213 @@
+mod common;
+
+use common::*;
+use koopa_contract::{
+    errors::KooPaaError,
+    state::{AjoGroup, AjoParticipant, FeeMode, PayoutOrder},
+};
+
+const UNASSIGNED: u8 = AjoParticipant::UNASSIGNED_ROUND;
+
+fn claim_rounds(group: &AjoGroup) -> Vec<u8> {
+    group.participants.iter().map(|p| p.claim_round).collect()
+}
+
+fn credits(group: &AjoGroup) -> Vec<u64> {
+    group.participants.iter().map(|p| p.credit).collect()
+}
+
+async fn contribute_all(env: &mut TestEnv, members: &[User], name: &str) {
+    for member in members {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn each_pot_goes_to_the_highest_discount() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "auction";
+    let args = GroupArgs {
+        payout_order: PayoutOrder::Auction,
+        ..GroupArgs::default()
+    };
+    let mut members = env.setup_group_with(name, &args, 1).await;
+
+    // Pots are only auctioned once the group is running
+    let ix = env.ix_place_payout_bid(&members[1], name, 30 * USDC);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::GroupNotStarted,
+    );
+
+    let last = env.create_user(10_000 * USDC).await;
+    let ix = env.ix_join(&last, name);
+    env.process(&[ix], &[&last.keypair]).await.unwrap();
+    members.push(last);
+    assert_eq!(claim_rounds(&env.group(name).await), vec![UNASSIGNED; 3]);
+
+    // Nobody can bid more than the other members put into the pot
+    let ix = env.ix_place_payout_bid(&members[1], name, 201 * USDC);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::InvalidBid,
+    );
+
+    for (member, discount) in [(&members[1], 30 * USDC), (&members[2], 20 * USDC)] {
+        let ix = env.ix_place_payout_bid(member, name, discount);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    contribute_all(&mut env, &members, name).await;
+    env.warp_days(7).await;
+    let ix = env.ix_place_payout_bid(&members[0], name, 40 * USDC);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::BiddingClosed,
+    );
+
+    let ix = env.ix_payout(&members[2], name);
+    assert_error(
+        env.process(&[ix], &[&members[2].keypair]).await,
+        KooPaaError::NotCurrentRecipient,
+    );
+
+    // The winner takes the pot minus their discount, split between the other two
+    let before = env.token_balance(&members[1].token_account).await;
+    let ix = env.ix_payout(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[1].token_account).await - before,
+        270 * USDC
+    );
+
+    let group = env.group(name).await;
+    assert_eq!(claim_rounds(&group), vec![UNASSIGNED, 0, UNASSIGNED]);
+    assert_eq!(credits(&group), vec![15 * USDC, 0, 15 * USDC]);
+    assert!(group.participants.iter().all(|p| p.bid == 0));
+
+    // Round two: a paid member is out, the remaining bidder wins
+    let ix = env.ix_place_payout_bid(&members[1], name, 10 * USDC);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::PositionAlreadyPaid,
+    );
+
+    let ix = env.ix_place_payout_bid(&members[2], name, 10 * USDC);
+    env.process(&[ix], &[&members[2].keypair]).await.unwrap();
+
+    contribute_all(&mut env, &members, name).await;
+    env.warp_days(7).await;
+    let ix = env.ix_payout(&members[2], name);
+    env.process(&[ix], &[&members[2].keypair]).await.unwrap();
+
+    // Round three: the last unpaid member takes the pot without bidding
+    contribute_all(&mut env, &members, name).await;
+    env.warp_days(7).await;
+    let ix = env.ix_payout(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(claim_rounds(&group), vec![2, 0, 1]);
+    assert_eq!(credits(&group), vec![20 * USDC, 5 * USDC, 15 * USDC]);
+
+    // Accumulated discount shares are paid out with the deposit
+    let before = env.token_balance(&members[0].token_account).await;
+    let ix = env.ix_withdraw_deposit(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[0].token_account).await - before,
+        25 * USDC
+    );
+}
+
+#[tokio::test]
+async fn bids_cannot_eat_into_the_payout_fee() {
+    let mut env = TestEnv::new(100, FeeMode::OnPayout).await;
+    let name = "fee-auction";
+    let args = GroupArgs {
+        payout_order: PayoutOrder::Auction,
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 2).await;
+
+    // A 300 pot pays a 30 fee, and the winner's own 100 is never discounted
+    let ix = env.ix_place_payout_bid(&members[1], name, 171 * USDC);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::InvalidBid,
+    );
+    let ix = env.ix_place_payout_bid(&members[1], name, 170 * USDC);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+
+    contribute_all(&mut env, &members, name).await;
+    env.warp_days(7).await;
+    let before = env.token_balance(&members[1].token_account).await;
+    let ix = env.ix_payout(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[1].token_account).await - before,
+        100 * USDC
+    );
+}
+
+#[tokio::test]
+async fn auction_positions_cannot_be_swapped() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "no-swap";
+    let args = GroupArgs {
+        payout_order: PayoutOrder::Auction,
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 2).await;
+
+    let ix = env.ix_propose_swap(&members[0], &members[1].pubkey(), name, 0);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::PayoutOrderMismatch,
+    );
+}
+
+#[tokio::test]
+async fn ejected_members_get_no_share_of_the_discount() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "auction-eject";
+    let args = GroupArgs {
+        payout_order: PayoutOrder::Auction,
+        num_participants: 4,
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 3).await;
+    let defaulter = &members[3];
+
+    contribute_all(&mut env, &members[..3], name).await;
+    env.warp_days(7).await;
+    env.warp_seconds(1).await;
+    let ix = env.ix_slash_defaulter(&members[0], name, &defaulter.pubkey());
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    let ix = env.ix_payout(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let ix = env.ix_place_payout_bid(&members[1], name, 30 * USDC);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+
+    env.warp_days(7).await;
+    for voter in &members[..2] {
+        let ix = env.ix_vote_to_eject(voter, name, &defaulter.pubkey());
+        env.process(&[ix], &[&voter.keypair]).await.unwrap();
+    }
+    contribute_all(&mut env, &members[..3], name).await;
+
+    // The discount is split between the two members still in the group
+    let before = env.token_balance(&members[1].token_account).await;
+    let ix = env.ix_payout(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[1].token_account).await - before,
+        270 * USDC
+    );
+    assert_eq!(
+        credits(&env.group(name).await),
+        vec![15 * USDC, 0, 15 * USDC, 0]
+    );
+}
@@ -0,0 +1,1285 @@
+#![allow(dead_code)]
+
+use anchor_lang::{
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_instruction,
+    },
+    AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    token::spl_token::{self, state::AccountState},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee, ExtensionType, StateWithExtensions},
+    },
+};
+use koopa_contract::{accounts, instruction, state::*, utils::*};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::InstructionError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+pub const DECIMALS: u8 = 6;
+pub const USDC: u64 = 1_000_000;
+pub const DAY: i64 = 24 * 60 * 60;
+
+// Anchor's entrypoint ties the account slice to the AccountInfo lifetime
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    koopa_contract::entry(program_id, accounts, data)
+}
+
+pub struct User {
+    pub keypair: Keypair,
+    pub token_account: Pubkey,
+}
+
+impl User {
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+}
+
+// Basis points and maximum fee of a Token-2022 transfer fee extension
+#[derive(Clone, Copy)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+// Arguments for create_ajo_group, defaulting to a weekly three-member group
+#[derive(Clone)]
+pub struct GroupArgs {
+    pub contribution_amount: u64,
+    pub contribution_interval: u16,
+    pub payout_interval: u16,
+    pub num_participants: u8,
+    pub payout_order: PayoutOrder,
+    pub late_policy: LatePolicy,
+    pub join_deadline: Option<i64>,
+}
+
+impl Default for GroupArgs {
+    fn default() -> Self {
+        Self {
+            contribution_amount: 100 * USDC,
+            contribution_interval: 7,
+            payout_interval: 7,
+            num_participants: 3,
+            payout_order: PayoutOrder::JoinOrder,
+            late_policy: LatePolicy::NONE,
+            join_deadline: None,
+        }
+    }
+}
+
+pub struct TestEnv {
+    pub ctx: ProgramTestContext,
+    pub admin: Keypair,
+    pub mint_authority: Keypair,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub transfer_fee: Option<TransferFee>,
+}
+
+impl TestEnv {
+    // Starts the program with an initialized global state, an allowed mint and its treasury
+    pub async fn new(fee_percentage: u8, fee_mode: FeeMode) -> Self {
+        Self::start(fee_percentage, fee_mode, None).await
+    }
+
+    // Same as `new`, but the group mint is a Token-2022 mint charging a transfer fee
+    pub async fn with_transfer_fee(
+        fee_percentage: u8,
+        fee_mode: FeeMode,
+        transfer_fee: TransferFee,
+    ) -> Self {
+        Self::start(fee_percentage, fee_mode, Some(transfer_fee)).await
+    }
+
+    async fn start(
+        fee_percentage: u8,
+        fee_mode: FeeMode,
+        transfer_fee: Option<TransferFee>,
+    ) -> Self {
+        let mut program_test = ProgramTest::new(
+            "koopa_contract",
+            koopa_contract::ID,
+            processor!(process_instruction),
+        );
+        program_test.prefer_bpf(false);
+
+        let ctx = program_test.start_with_context().await;
+        let mut env = Self {
+            ctx,
+            admin: Keypair::new(),
+            mint_authority: Keypair::new(),
+            mint: Pubkey::default(),
+            token_program: match transfer_fee {
+                Some(_) => spl_token_2022::ID,
+                None => spl_token::ID,
+            },
+            transfer_fee,
+        };
+
+        env.airdrop(&env.admin.pubkey(), 10_000_000_000).await;
+        env.mint = env.create_mint(DECIMALS).await;
+
+        let initialize = env.ix_initialize(fee_percentage, fee_mode);
+        let add_mint = env.ix_add_allowed_mint(&env.mint);
+        let treasury = env.ix_initialize_treasury(&env.mint);
+        let admin = env.admin.insecure_clone();
+        env.process(&[initialize, add_mint, treasury], &[&admin])
+            .await
+            .unwrap();
+
+        env
+    }
+
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        // A fresh blockhash keeps retried transactions from being deduplicated
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        self.ctx.banks_client.process_transaction(transaction).await
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let payer = self.ctx.payer.insecure_clone();
+        let ix = system_instruction::transfer(&payer.pubkey(), to, lamports);
+        self.process(&[ix], &[]).await.unwrap();
+    }
+
+    pub async fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let payer = self.ctx.payer.pubkey();
+        let authority = self.mint_authority.pubkey();
+
+        let extensions: Vec<ExtensionType> = match self.transfer_fee {
+            Some(_) => vec![ExtensionType::TransferFeeConfig],
+            None => vec![],
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)
+                .unwrap();
+
+        let mut instructions = vec![system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &self.token_program,
+        )];
+        if let Some(fee) = self.transfer_fee {
+            instructions.push(
+                transfer_fee::instruction::initialize_transfer_fee_config(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    Some(&authority),
+                    Some(&authority),
+                    fee.basis_points,
+                    fee.maximum_fee,
+                )
+                .unwrap(),
+            );
+        }
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &authority,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        );
+        self.process(&instructions, &[&mint]).await.unwrap();
+
+        mint.pubkey()
+    }
+
+    pub async fn create_token_account(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let payer = self.ctx.payer.pubkey();
+
+        let extensions: Vec<ExtensionType> = match self.transfer_fee {
+            Some(_) => vec![ExtensionType::TransferFeeAmount],
+            None => vec![],
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extensions)
+                .unwrap();
+
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &self.token_program,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &self.token_program,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&account]).await.unwrap();
+
+        account.pubkey()
+    }
+
+    pub async fn mint_to(&mut self, mint: &Pubkey, account: &Pubkey, amount: u64) {
+        let mint_authority = self.mint_authority.insecure_clone();
+        let ix = spl_token_2022::instruction::mint_to(
+            &self.token_program,
+            mint,
+            account,
+            &mint_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[ix], &[&mint_authority]).await.unwrap();
+    }
+
+    // A funded wallet with a token account holding `amount` of the test mint
+    pub async fn create_user(&mut self, amount: u64) -> User {
+        let keypair = Keypair::new();
+        self.airdrop(&keypair.pubkey(), 1_000_000_000).await;
+
+        let mint = self.mint;
+        let token_account = self.create_token_account(&keypair.pubkey(), &mint).await;
+        self.mint_to(&mint, &token_account, amount).await;
+
+        User {
+            keypair,
+            token_account,
+        }
+    }
+
+    pub async fn token_balance(&mut self, account: &Pubkey) -> u64 {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(*account)
+            .await
+            .unwrap()
+            .unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    pub async fn fetch<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap();
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn group(&mut self, name: &str) -> AjoGroup {
+        self.fetch(&group_pda(name)).await
+    }
+
+    pub async fn global_state(&mut self) -> GlobalState {
+        self.fetch(&global_state_pda()).await
+    }
+
+    pub async fn treasury_state(&mut self, mint: &Pubkey) -> TreasuryState {
+        self.fetch(&treasury_state_pda(mint)).await
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    pub async fn warp_seconds(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    pub async fn warp_days(&mut self, days: i64) {
+        self.warp_seconds(days * DAY).await;
+    }
+
+    pub async fn warp_slots(&mut self, slots: u64) {
+        let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        self.ctx.warp_to_slot(clock.slot + slots).unwrap();
+    }
+
+    // Writes a group exactly as the first release laid it out: weekly 100 USDC rounds,
+    // the creator's 5 USDC deposit, and `members` in join order with the creator first
+    pub async fn install_legacy_group(&mut self, name: &str, members: &[&User], started: bool) {
+        self.install_legacy_group_with(name, members, started, (7, 7))
+            .await;
+    }
+
+    // Same as `install_legacy_group` with (contribution, payout) intervals the first
+    // release never validated against each other
+    pub async fn install_legacy_group_with(
+        &mut self,
+        name: &str,
+        members: &[&User],
+        started: bool,
+        intervals: (u16, u16),
+    ) {
+        let mut data = AjoGroup::DISCRIMINATOR.to_vec();
+        data.extend((name.len() as u32).to_le_bytes());
+        data.extend(name.as_bytes());
+        data.extend((5 * USDC).to_le_bytes()); // security_deposit
+        data.extend((100 * USDC).to_le_bytes()); // contribution_amount
+        data.extend(intervals.0.to_le_bytes()); // contribution_interval
+        data.extend(intervals.1.to_le_bytes()); // payout_interval
+                                                // The first release stored one less than the size asked for, and started the
+                                                // group as soon as that many had joined
+        let num_participants = members.len() as u8 + if started { 1 } else { 2 };
+        data.push(num_participants - 1); // num_participants
+        data.extend((members.len() as u32).to_le_bytes());
+        for member in members {
+            data.extend(member.pubkey().to_bytes());
+            data.extend([0, 0, 255]); // claim_round, contribution_round, bump
+        }
+        if started {
+            data.push(1);
+            data.extend(self.now().await.to_le_bytes()); // start_timestamp
+        } else {
+            data.push(0);
+        }
+        data.push(0); // payout_round
+        data.extend(0u32.to_le_bytes()); // close_votes
+        data.push(0); // is_closed
+        data.push(find_group_pda(name, &koopa_contract::ID).1); // bumps
+
+        // The first release sized groups at 1580 bytes plus the name
+        data.resize(1580 + name.len(), 0);
+
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let group = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: koopa_contract::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&group_pda(name), &group.into());
+    }
+
+    // Rewrites the global state exactly as the first release laid it out, keeping the admin
+    pub async fn install_legacy_global_state(&mut self, total_groups: u64) {
+        let bumps = self.global_state().await.bumps;
+        let mut data = GlobalState::DISCRIMINATOR.to_vec();
+        data.extend(total_groups.to_le_bytes()); // total_groups
+        data.extend((3 * USDC).to_le_bytes()); // total_revenue
+        data.extend(1u64.to_le_bytes()); // active_groups
+        data.extend(0u64.to_le_bytes()); // completed_groups
+        data.extend(self.admin.pubkey().to_bytes()); // admin
+        data.push(10); // fee_percentage
+        data.extend((5 * USDC).to_le_bytes()); // creator_security_deposit
+        data.extend((2 * USDC).to_le_bytes()); // joiner_security_deposit
+        data.push(bumps); // bumps
+
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let global_state = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: koopa_contract::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx
+            .set_account(&global_state_pda(), &global_state.into());
+    }
+
+    // A name-seeded legacy vault owned by `owner` holding `amount`
+    pub async fn install_legacy_vault(&mut self, name: &str, owner: &Pubkey, amount: u64) {
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: self.mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+
+        let legacy_vault = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx
+            .set_account(&legacy_vault_pda(name), &legacy_vault.into());
+    }
+
+    // Empties the SlotHashes sysvar, as a validator that has not recorded any slots would
+    pub async fn clear_slot_hashes(&mut self) {
+        let slot_hashes = Account {
+            lamports: 1,
+            data: vec![],
+            owner: sysvar::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx
+            .set_account(&sysvar::slot_hashes::ID, &slot_hashes.into());
+    }
+
+    // Creates a group and has `joiners` join it, returning the creator and joiners
+    pub async fn setup_group(
+        &mut self,
+        name: &str,
+        contribution_amount: u64,
+        interval_days: u16,
+        joiners: usize,
+    ) -> Vec<User> {
+        self.setup_partial_group(
+            name,
+            contribution_amount,
+            interval_days,
+            joiners + 1,
+            joiners,
+        )
+        .await
+    }
+
+    // Same as `setup_group`, but for a group of `size` that may still be recruiting
+    pub async fn setup_partial_group(
+        &mut self,
+        name: &str,
+        contribution_amount: u64,
+        interval_days: u16,
+        size: usize,
+        joiners: usize,
+    ) -> Vec<User> {
+        let args = GroupArgs {
+            contribution_amount,
+            contribution_interval: interval_days,
+            payout_interval: interval_days,
+            num_participants: size as u8,
+            ..GroupArgs::default()
+        };
+        self.setup_group_with(name, &args, joiners).await
+    }
+
+    // Creates a group from `args` and has `joiners` join it
+    pub async fn setup_group_with(
+        &mut self,
+        name: &str,
+        args: &GroupArgs,
+        joiners: usize,
+    ) -> Vec<User> {
+        let creator = self.create_user(10_000 * USDC).await;
+        let ix = self.ix_create_group_with(&creator, name, args);
+        self.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+        let mut members = vec![creator];
+        for _ in 0..joiners {
+            let joiner = self.create_user(10_000 * USDC).await;
+            let ix = self.ix_join(&joiner, name);
+            self.process(&[ix], &[&joiner.keypair]).await.unwrap();
+            members.push(joiner);
+        }
+
+        members
+    }
+
+    pub fn ix_initialize(&self, fee_percentage: u8, fee_mode: FeeMode) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::Initialize {
+                global_state: global_state_pda(),
+                admin: self.admin.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Initialize {
+                fee_percentage,
+                fee_mode,
+            }
+            .data(),
+        }
+    }
+
+    pub fn ix_add_allowed_mint(&self, mint: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::AddAllowedMint {
+                global_state: global_state_pda(),
+                allowed_mint: allowed_mint_pda(mint),
+                token_mint: *mint,
+                admin: self.admin.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AddAllowedMint {}.data(),
+        }
+    }
+
+    pub fn ix_initialize_treasury(&self, mint: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::InitializeTreasury {
+                global_state: global_state_pda(),
+                treasury_token_account: treasury_pda(mint),
+                treasury_state: treasury_state_pda(mint),
+                token_mint: *mint,
+                admin: self.admin.pubkey(),
+                token_program: self.token_program,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeTreasury {}.data(),
+        }
+    }
+
+    pub fn ix_create_group(
+        &self,
+        creator: &User,
+        name: &str,
+        contribution_amount: u64,
+        contribution_interval: u16,
+        payout_interval: u16,
+        num_participants: u8,
+    ) -> Instruction {
+        let args = GroupArgs {
+            contribution_amount,
+            contribution_interval,
+            payout_interval,
+            num_participants,
+            ..GroupArgs::default()
+        };
+        self.ix_create_group_with(creator, name, &args)
+    }
+
+    pub fn ix_create_group_with(
+        &self,
+        creator: &User,
+        name: &str,
+        args: &GroupArgs,
+    ) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::CreateAjoGroup {
+                ajo_group: group,
+                creator: creator.pubkey(),
+                global_state: global_state_pda(),
+                allowed_mint: allowed_mint_pda(&self.mint),
+                token_mint: self.mint,
+                creator_token_account: creator.token_account,
+                group_token_vault: vault_pda(&group),
+                token_program: self.token_program,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreateAjoGroup {
+                name: name.to_string(),
+                contribution_amount: args.contribution_amount,
+                contribution_interval: args.contribution_interval,
+                payout_interval: args.payout_interval,
+                num_participants: args.num_participants,
+                payout_order: args.payout_order,
+                late_policy: args.late_policy,
+                join_deadline: args.join_deadline,
+            }
+            .data(),
+        }
+    }
+
+    pub fn ix_join(&self, participant: &User, name: &str) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::JoinAjoGroup {
+                ajo_group: group,
+                participant: participant.pubkey(),
+                global_state: global_state_pda(),
+                token_mint: self.mint,
+                participant_token_account: participant.token_account,
+                group_token_vault: vault_pda(&group),
+                slot_hashes: sysvar::slot_hashes::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::JoinAjoGroup {}.data(),
+        }
+    }
+
+    pub fn ix_vote_start_early(&self, participant: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::VoteStartEarly {
+                ajo_group: group_pda(name),
+                participant: participant.pubkey(),
+                global_state: global_state_pda(),
+                slot_hashes: sysvar::slot_hashes::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::VoteStartEarly {}.data(),
+        }
+    }
+
+    pub fn ix_leave(&self, participant: &User, name: &str) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::LeaveAjoGroup {
+                ajo_group: group,
+                group_token_vault: vault_pda(&group),
+                participant: participant.pubkey(),
+                participant_token_account: participant.token_account,
+                token_mint: self.mint,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::LeaveAjoGroup {}.data(),
+        }
+    }
+
+    pub fn ix_contribute(&self, contributor: &User, name: &str) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::Contribute {
+                ajo_group: group,
+                contributor: contributor.pubkey(),
+                contributor_token_account: contributor.token_account,
+                group_token_vault: vault_pda(&group),
+                global_state: global_state_pda(),
+                treasury_token_account: treasury_pda(&self.mint),
+                treasury_state: treasury_state_pda(&self.mint),
+                token_mint: self.mint,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Contribute {}.data(),
+        }
+    }
+
+    pub fn ix_contribute_ahead(&self, contributor: &User, name: &str, rounds: u8) -> Instruction {
+        Instruction {
+            data: instruction::ContributeAhead { rounds }.data(),
+            ..self.ix_contribute(contributor, name)
+        }
+    }
+
+    pub fn ix_payout(&self, recipient: &User, name: &str) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::Payout {
+                ajo_group: group,
+                group_token_vault: vault_pda(&group),
+                recipient: recipient.pubkey(),
+                recipient_token_account: recipient.token_account,
+                global_state: global_state_pda(),
+                treasury_token_account: treasury_pda(&self.mint),
+                treasury_state: treasury_state_pda(&self.mint),
+                token_mint: self.mint,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::Payout {}.data(),
+        }
+    }
+
+    pub fn ix_update_global_config(&self, params: GlobalConfigParams) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.admin_update_accounts(),
+            data: instruction::UpdateGlobalConfig { params }.data(),
+        }
+    }
+
+    pub fn ix_propose_admin(&self, new_admin: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.admin_update_accounts(),
+            data: instruction::ProposeAdmin {
+                new_admin: *new_admin,
+            }
+            .data(),
+        }
+    }
+
+    pub fn ix_accept_admin(&self, new_admin: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::AcceptAdmin {
+                global_state: global_state_pda(),
+                new_admin: *new_admin,
+            }
+            .to_account_metas(None),
+            data: instruction::AcceptAdmin {}.data(),
+        }
+    }
+
+    pub fn ix_pause(&self) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.admin_update_accounts(),
+            data: instruction::Pause {}.data(),
+        }
+    }
+
+    pub fn ix_unpause(&self) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.admin_update_accounts(),
+            data: instruction::Unpause {}.data(),
+        }
+    }
+
+    pub fn ix_pause_group(&self, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.group_admin_update_accounts(name),
+            data: instruction::PauseGroup {}.data(),
+        }
+    }
+
+    pub fn ix_resume_group(&self, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.group_admin_update_accounts(name),
+            data: instruction::ResumeGroup {}.data(),
+        }
+    }
+
+    pub fn ix_remove_allowed_mint(&self, mint: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::RemoveAllowedMint {
+                global_state: global_state_pda(),
+                allowed_mint: allowed_mint_pda(mint),
+                admin: self.admin.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::RemoveAllowedMint {}.data(),
+        }
+    }
+
+    pub fn ix_set_treasury_multisig(&self, treasury_multisig: Option<Pubkey>) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: self.admin_update_accounts(),
+            data: instruction::SetTreasuryMultisig { treasury_multisig }.data(),
+        }
+    }
+
+    pub fn ix_withdraw_treasury(
+        &self,
+        authority: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        self.ix_withdraw_treasury_in(&self.mint, authority, destination, amount)
+    }
+
+    // Withdraws from the treasury of `mint` rather than the group mint's
+    pub fn ix_withdraw_treasury_in(
+        &self,
+        mint: &Pubkey,
+        authority: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::WithdrawTreasury {
+                global_state: global_state_pda(),
+                authority: *authority,
+                treasury_token_account: treasury_pda(mint),
+                treasury_state: treasury_state_pda(mint),
+                destination_token_account: *destination,
+                token_mint: *mint,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::WithdrawTreasury { amount }.data(),
+        }
+    }
+
+    pub fn ix_claim_round(&self, recipient: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::ClaimRound {
+                ajo_group: group_pda(name),
+                recipient: recipient.pubkey(),
+                global_state: global_state_pda(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimRound {}.data(),
+        }
+    }
+
+    pub fn ix_slash_defaulter(&self, caller: &User, name: &str, member: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::SlashDefaulter {
+                ajo_group: group_pda(name),
+                global_state: global_state_pda(),
+                caller: caller.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::SlashDefaulter { member: *member }.data(),
+        }
+    }
+
+    pub fn ix_skip_vacant_position(&self, caller: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::SkipVacantPosition {
+                ajo_group: group_pda(name),
+                caller: caller.pubkey(),
+                global_state: global_state_pda(),
+            }
+            .to_account_metas(None),
+            data: instruction::SkipVacantPosition {}.data(),
+        }
+    }
+
+    pub fn ix_vote_to_eject(&self, voter: &User, name: &str, member: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::VoteToEject {
+                ajo_group: group_pda(name),
+                voter: voter.pubkey(),
+                global_state: global_state_pda(),
+            }
+            .to_account_metas(None),
+            data: instruction::VoteToEject { member: *member }.data(),
+        }
+    }
+
+    pub fn ix_admit_replacement(
+        &self,
+        replacement: &User,
+        name: &str,
+        member: &Pubkey,
+    ) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::AdmitReplacement {
+                ajo_group: group,
+                replacement: replacement.pubkey(),
+                global_state: global_state_pda(),
+                token_mint: self.mint,
+                replacement_token_account: replacement.token_account,
+                group_token_vault: vault_pda(&group),
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::AdmitReplacement { member: *member }.data(),
+        }
+    }
+
+    pub fn ix_set_payout_order(
+        &self,
+        creator: &User,
+        name: &str,
+        order: Vec<Pubkey>,
+    ) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::SetPayoutOrder {
+                ajo_group: group_pda(name),
+                creator: creator.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::SetPayoutOrder { fixed_order: order }.data(),
+        }
+    }
+
+    pub fn ix_place_payout_bid(
+        &self,
+        participant: &User,
+        name: &str,
+        discount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::PlacePayoutBid {
+                ajo_group: group_pda(name),
+                participant: participant.pubkey(),
+                global_state: global_state_pda(),
+            }
+            .to_account_metas(None),
+            data: instruction::PlacePayoutBid { discount }.data(),
+        }
+    }
+
+    pub fn ix_reveal_payout_order(&self, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::RevealPayoutOrder {
+                ajo_group: group_pda(name),
+                slot_hashes: sysvar::slot_hashes::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::RevealPayoutOrder {}.data(),
+        }
+    }
+
+    pub fn ix_propose_swap(
+        &self,
+        proposer: &User,
+        counterparty: &Pubkey,
+        name: &str,
+        sweetener: u64,
+    ) -> Instruction {
+        let group = group_pda(name);
+        let proposal = swap_pda(&group, &proposer.pubkey());
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::ProposeSwap {
+                ajo_group: group,
+                swap_proposal: proposal,
+                swap_escrow: swap_escrow_pda(&proposal),
+                proposer: proposer.pubkey(),
+                proposer_token_account: proposer.token_account,
+                token_mint: self.mint,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ProposeSwap {
+                counterparty: *counterparty,
+                sweetener,
+            }
+            .data(),
+        }
+    }
+
+    pub fn ix_accept_swap(
+        &self,
+        counterparty: &User,
+        proposer: &Pubkey,
+        name: &str,
+    ) -> Instruction {
+        let group = group_pda(name);
+        let proposal = swap_pda(&group, proposer);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::AcceptSwap {
+                ajo_group: group,
+                swap_proposal: proposal,
+                swap_escrow: swap_escrow_pda(&proposal),
+                counterparty: counterparty.pubkey(),
+                counterparty_token_account: counterparty.token_account,
+                proposer: *proposer,
+                token_mint: self.mint,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::AcceptSwap {}.data(),
+        }
+    }
+
+    pub fn ix_cancel_swap(&self, proposer: &User, name: &str) -> Instruction {
+        let group = group_pda(name);
+        let proposal = swap_pda(&group, &proposer.pubkey());
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::CancelSwap {
+                ajo_group: group,
+                swap_proposal: proposal,
+                swap_escrow: swap_escrow_pda(&proposal),
+                proposer: proposer.pubkey(),
+                proposer_token_account: proposer.token_account,
+                token_mint: self.mint,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::CancelSwap {}.data(),
+        }
+    }
+
+    pub fn ix_close_group(&self, participant: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::CloseAjoGroup {
+                ajo_group: group_pda(name),
+                participant: participant.pubkey(),
+                global_state: global_state_pda(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CloseAjoGroup {}.data(),
+        }
+    }
+
+    pub fn ix_cancel_group(&self, creator: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::CancelAjoGroup {
+                ajo_group: group_pda(name),
+                creator: creator.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::CancelAjoGroup {}.data(),
+        }
+    }
+
+    pub fn ix_expire_group(&self, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::ExpireGroup {
+                ajo_group: group_pda(name),
+                global_state: global_state_pda(),
+            }
+            .to_account_metas(None),
+            data: instruction::ExpireGroup {}.data(),
+        }
+    }
+
+    pub fn ix_withdraw_deposit(&self, participant: &User, name: &str) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::WithdrawSecurityDeposit {
+                ajo_group: group,
+                group_token_vault: vault_pda(&group),
+                participant: participant.pubkey(),
+                participant_token_account: participant.token_account,
+                token_mint: self.mint,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::WithdrawSecurityDeposit {}.data(),
+        }
+    }
+
+    pub fn ix_opt_into_next_cycle(&self, participant: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::OptIntoNextCycle {
+                ajo_group: group_pda(name),
+                participant: participant.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::OptIntoNextCycle {}.data(),
+        }
+    }
+
+    pub fn ix_opt_out_of_next_cycle(&self, participant: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::OptOutOfNextCycle {
+                ajo_group: group_pda(name),
+                participant: participant.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::OptOutOfNextCycle {}.data(),
+        }
+    }
+
+    pub fn ix_set_next_cycle_terms(
+        &self,
+        creator: &User,
+        name: &str,
+        contribution_amount: u64,
+        payout_order: PayoutOrder,
+    ) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::SetNextCycleTerms {
+                ajo_group: group_pda(name),
+                creator: creator.pubkey(),
+            }
+            .to_account_metas(None),
+            data: instruction::SetNextCycleTerms {
+                contribution_amount,
+                payout_order,
+            }
+            .data(),
+        }
+    }
+
+    pub fn ix_start_next_cycle(&self, creator: &User, name: &str) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::StartNextCycle {
+                ajo_group: group_pda(name),
+                creator: creator.pubkey(),
+                global_state: global_state_pda(),
+            }
+            .to_account_metas(None),
+            data: instruction::StartNextCycle {
+                join_deadline: None,
+            }
+            .data(),
+        }
+    }
+
+    pub fn ix_migrate_global_state(&self, admin: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::MigrateGlobalState {
+                global_state: global_state_pda(),
+                admin: *admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::MigrateGlobalState {}.data(),
+        }
+    }
+
+    pub fn ix_migrate_group_vault(&self, creator: &Pubkey, name: &str) -> Instruction {
+        let group = group_pda(name);
+        Instruction {
+            program_id: koopa_contract::ID,
+            accounts: accounts::MigrateGroupVault {
+                ajo_group: group,
+                legacy_vault: legacy_vault_pda(name),
+                group_token_vault: vault_pda(&group),
+                token_mint: self.mint,
+                creator: *creator,
+                global_state: global_state_pda(),
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::MigrateGroupVault {
+                name: name.to_string(),
+            }
+            .data(),
+        }
+    }
+
+    fn admin_update_accounts(&self) -> Vec<AccountMeta> {
+        accounts::AdminUpdate {
+            global_state: global_state_pda(),
+            admin: self.admin.pubkey(),
+        }
+        .to_account_metas(None)
+    }
+
+    fn group_admin_update_accounts(&self, name: &str) -> Vec<AccountMeta> {
+        accounts::GroupAdminUpdate {
+            global_state: global_state_pda(),
+            admin: self.admin.pubkey(),
+            ajo_group: group_pda(name),
+        }
+        .to_account_metas(None)
+    }
+}
+
+pub fn global_state_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"global-state"], &koopa_contract::ID).0
+}
+
+pub fn group_pda(name: &str) -> Pubkey {
+    find_group_pda(name, &koopa_contract::ID).0
+}
+
+pub fn vault_pda(group: &Pubkey) -> Pubkey {
+    find_group_vault_pda(group, &koopa_contract::ID).0
+}
+
+// Vaults created before the seeds were unified were keyed by the group name
+pub fn legacy_vault_pda(name: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"group-vault", name.as_bytes()], &koopa_contract::ID).0
+}
+
+pub fn swap_pda(group: &Pubkey, proposer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"swap", group.as_ref(), proposer.as_ref()],
+        &koopa_contract::ID,
+    )
+    .0
+}
+
+pub fn swap_escrow_pda(proposal: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"swap-escrow", proposal.as_ref()], &koopa_contract::ID).0
+}
+
+pub fn treasury_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"treasury", global_state_pda().as_ref(), mint.as_ref()],
+        &koopa_contract::ID,
+    )
+    .0
+}
+
+pub fn treasury_state_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"treasury-state",
+            global_state_pda().as_ref(),
+            mint.as_ref(),
+        ],
+        &koopa_contract::ID,
+    )
+    .0
+}
+
+pub fn allowed_mint_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allowed-mint", mint.as_ref()], &koopa_contract::ID).0
+}
+
+// Points every meta for `from` at `to`, for feeding an instruction the wrong account
+pub fn replace_account(ix: &mut Instruction, from: &Pubkey, to: &Pubkey) {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *from) {
+        meta.pubkey = *to;
+    }
+}
+
+pub fn assert_error(result: Result<(), BanksClientError>, expected: impl Into<u32>) {
+    let expected: u32 = expected.into();
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, expected, "unexpected error code"),
+        other => panic!("expected custom error {expected}, got {other:?}"),
+    }
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use common::*;
+use koopa_contract::{errors::KooPaaError, state::*};
+
+#[tokio::test]
+async fn completed_group_rolls_into_a_new_cycle() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "rolling";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (creator, stayer, leaver) = (&members[0], &members[1], &members[2]);
+
+    let ix = env.ix_opt_into_next_cycle(stayer, name);
+    assert_error(
+        env.process(&[ix], &[&stayer.keypair]).await,
+        KooPaaError::CycleNotCompleted,
+    );
+
+    for recipient in &members {
+        for member in &members {
+            let ix = env.ix_contribute(member, name);
+            env.process(&[ix], &[&member.keypair]).await.unwrap();
+        }
+        env.warp_days(7).await;
+        let ix = env.ix_payout(recipient, name);
+        env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    }
+
+    // The next cycle's terms are announced before anyone opts in to them
+    let ix = env.ix_set_next_cycle_terms(creator, name, 50 * USDC, PayoutOrder::JoinOrder);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    let ix = env.ix_opt_into_next_cycle(stayer, name);
+    env.process(&[ix], &[&stayer.keypair]).await.unwrap();
+    let ix = env.ix_opt_into_next_cycle(stayer, name);
+    assert_error(
+        env.process(&[ix], &[&stayer.keypair]).await,
+        KooPaaError::AlreadyOptedIn,
+    );
+
+    // Opting in keeps the deposit in the vault for the next cycle
+    let ix = env.ix_withdraw_deposit(stayer, name);
+    assert_error(
+        env.process(&[ix], &[&stayer.keypair]).await,
+        KooPaaError::OptedIntoNextCycle,
+    );
+
+    let ix = env.ix_start_next_cycle(creator, name);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::NextCycleNotSettled,
+    );
+
+    // Opting out is withdrawing the deposit as usual
+    let ix = env.ix_withdraw_deposit(leaver, name);
+    env.process(&[ix], &[&leaver.keypair]).await.unwrap();
+
+    let ix = env.ix_start_next_cycle(stayer, name);
+    assert_error(
+        env.process(&[ix], &[&stayer.keypair]).await,
+        KooPaaError::OnlyCreator,
+    );
+
+    let ix = env.ix_start_next_cycle(creator, name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(group.status, GroupStatus::Recruiting);
+    assert_eq!(group.cycle, 2);
+    assert_eq!(group.contribution_amount, 50 * USDC);
+    assert_eq!(group.payout_round, 0);
+    let carried: Vec<_> = group.participants.iter().map(|p| p.pubkey).collect();
+    assert_eq!(carried, vec![creator.pubkey(), stayer.pubkey()]);
+    assert_eq!(group.participants[0].security_deposit, 5 * USDC);
+    assert_eq!(group.participants[1].security_deposit, 2 * USDC);
+
+    // The open slot is refilled, which starts the new cycle
+    let newcomer = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&newcomer, name);
+    env.process(&[ix], &[&newcomer.keypair]).await.unwrap();
+    assert_eq!(env.group(name).await.status, GroupStatus::Active);
+    assert_eq!(env.global_state().await.active_groups, 1);
+
+    let before = env.token_balance(&stayer.token_account).await;
+    let ix = env.ix_contribute(stayer, name);
+    env.process(&[ix], &[&stayer.keypair]).await.unwrap();
+    assert_eq!(
+        before - env.token_balance(&stayer.token_account).await,
+        50 * USDC
+    );
+}
+
+#[tokio::test]
+async fn changed_terms_and_opting_out_release_the_deposit() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "reconsider";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (creator, stayer, leaver) = (&members[0], &members[1], &members[2]);
+
+    for recipient in &members {
+        for member in &members {
+            let ix = env.ix_contribute(member, name);
+            env.process(&[ix], &[&member.keypair]).await.unwrap();
+        }
+        env.warp_days(7).await;
+        let ix = env.ix_payout(recipient, name);
+        env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    }
+
+    let ix = env.ix_opt_out_of_next_cycle(leaver, name);
+    assert_error(
+        env.process(&[ix], &[&leaver.keypair]).await,
+        KooPaaError::NotOptedIn,
+    );
+
+    for member in [stayer, leaver] {
+        let ix = env.ix_opt_into_next_cycle(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // A member who opted in can back out and take their deposit after all
+    let ix = env.ix_opt_out_of_next_cycle(leaver, name);
+    env.process(&[ix], &[&leaver.keypair]).await.unwrap();
+    let ix = env.ix_withdraw_deposit(leaver, name);
+    env.process(&[ix], &[&leaver.keypair]).await.unwrap();
+
+    // Restating the same terms keeps the opt-ins, new ones clear them
+    let ix = env.ix_set_next_cycle_terms(stayer, name, 100 * USDC, PayoutOrder::JoinOrder);
+    assert_error(
+        env.process(&[ix], &[&stayer.keypair]).await,
+        KooPaaError::OnlyCreator,
+    );
+    let ix = env.ix_set_next_cycle_terms(creator, name, 100 * USDC, PayoutOrder::JoinOrder);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+    assert_eq!(
+        env.group(name).await.next_cycle_members,
+        vec![stayer.pubkey()]
+    );
+
+    let ix = env.ix_set_next_cycle_terms(creator, name, 200 * USDC, PayoutOrder::JoinOrder);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+    assert!(env.group(name).await.next_cycle_members.is_empty());
+
+    let ix = env.ix_start_next_cycle(creator, name);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::NextCycleNotSettled,
+    );
+
+    let before = env.token_balance(&stayer.token_account).await;
+    let ix = env.ix_withdraw_deposit(stayer, name);
+    env.process(&[ix], &[&stayer.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&stayer.token_account).await - before,
+        2 * USDC
+    );
+}
+
+#[tokio::test]
+async fn ejected_creator_cannot_roll_the_group_over() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "deposed";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (creator, others) = (&members[0], &members[1..]);
+
+    // The creator never pays, is slashed after round one and voted out after round two
+    for member in others {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    env.warp_seconds(1).await;
+    let ix = env.ix_slash_defaulter(&others[0], name, &creator.pubkey());
+    env.process(&[ix], &[&others[0].keypair]).await.unwrap();
+    env.warp_days(7).await;
+    for voter in others {
+        let ix = env.ix_vote_to_eject(voter, name, &creator.pubkey());
+        env.process(&[ix], &[&voter.keypair]).await.unwrap();
+    }
+
+    let replacement = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_admit_replacement(&replacement, name, &creator.pubkey());
+    env.process(&[ix], &[&replacement.keypair]).await.unwrap();
+    for member in others {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    for recipient in [&replacement, &others[0]] {
+        let ix = env.ix_payout(recipient, name);
+        env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    let ix = env.ix_payout(&others[1], name);
+    env.process(&[ix], &[&others[1].keypair]).await.unwrap();
+    assert_eq!(env.group(name).await.status, GroupStatus::Completed);
+
+    // The seat the creator lost is no longer theirs to carry into a new cycle
+    let ix = env.ix_start_next_cycle(creator, name);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::NotParticipant,
+    );
+}
@@ -0,0 +1,376 @@
+mod common;
+
+use common::*;
+use koopa_contract::{errors::KooPaaError, state::FeeMode};
+
+#[tokio::test]
+async fn defaulter_is_slashed_until_caught_up() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "slash";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (creator, keeper) = (&members[0], &members[2]);
+
+    let ix = env.ix_slash_defaulter(keeper, name, &creator.pubkey());
+    assert_error(
+        env.process(&[ix], &[&keeper.keypair]).await,
+        KooPaaError::NotInDefault,
+    );
+
+    for member in &members[1..] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // Round one had the whole first week; round two is open but not yet due
+    env.warp_days(7).await;
+    env.warp_seconds(1).await;
+
+    let ix = env.ix_slash_defaulter(keeper, name, &members[1].pubkey());
+    assert_error(
+        env.process(&[ix], &[&keeper.keypair]).await,
+        KooPaaError::NotInDefault,
+    );
+
+    // Anyone may slash once the round is overdue; the whole deposit goes to the pot
+    let ix = env.ix_slash_defaulter(keeper, name, &creator.pubkey());
+    env.process(&[ix], &[&keeper.keypair]).await.unwrap();
+
+    let participant = env.group(name).await.participants[0].clone();
+    assert!(participant.defaulted);
+    assert_eq!(participant.slashed, 5 * USDC);
+    assert_eq!(participant.security_deposit, 0);
+
+    let ix = env.ix_slash_defaulter(keeper, name, &creator.pubkey());
+    assert_error(
+        env.process(&[ix], &[&keeper.keypair]).await,
+        KooPaaError::AlreadySlashed,
+    );
+
+    let ix = env.ix_payout(creator, name);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::MemberDefaulted,
+    );
+
+    let ix = env.ix_contribute(creator, name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    let participant = env.group(name).await.participants[0].clone();
+    assert!(!participant.defaulted);
+    assert_eq!(participant.slashed, 0);
+    assert_eq!(participant.security_deposit, 5 * USDC);
+
+    let ix = env.ix_payout(creator, name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn members_paying_inside_the_window_are_never_slashed() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "punctual";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    // Every round is paid in the last hour of its window and each pot goes out right on time
+    for recipient in &members {
+        env.warp_seconds(7 * DAY - 60 * 60).await;
+        for member in &members {
+            let ix = env.ix_contribute(member, name);
+            env.process(&[ix], &[&member.keypair]).await.unwrap();
+        }
+
+        env.warp_seconds(60 * 60).await;
+        for member in &members {
+            let ix = env.ix_slash_defaulter(recipient, name, &member.pubkey());
+            assert_error(
+                env.process(&[ix], &[&recipient.keypair]).await,
+                KooPaaError::NotInDefault,
+            );
+        }
+
+        let ix = env.ix_payout(recipient, name);
+        env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    }
+
+    let group = env.group(name).await;
+    assert!(group
+        .participants
+        .iter()
+        .all(|p| !p.defaulted && p.slashed == 0));
+}
+
+#[tokio::test]
+async fn slashed_deposit_covers_the_pot_until_paid_back() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "cover";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (recipient, defaulter) = (&members[0], &members[2]);
+
+    for member in &members[..2] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(8).await;
+
+    let ix = env.ix_payout(recipient, name);
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::NotAllContributed,
+    );
+
+    // The whole 2 USDC deposit goes into the pot in place of the missed round
+    let ix = env.ix_slash_defaulter(recipient, name, &defaulter.pubkey());
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+
+    let before = env.token_balance(&recipient.token_account).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&recipient.token_account).await - before,
+        202 * USDC
+    );
+    assert_eq!(env.group(name).await.participants[2].covered, 2 * USDC);
+
+    // Paying the round late wins the deposit back, the rest is owed to the recipient
+    let ix = env.ix_contribute(defaulter, name);
+    env.process(&[ix], &[&defaulter.keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(group.participants[0].credit, 98 * USDC);
+    let participant = &group.participants[2];
+    assert!(!participant.defaulted);
+    assert_eq!(participant.slashed, 0);
+    assert_eq!(participant.covered, 0);
+    assert_eq!(participant.security_deposit, 2 * USDC);
+}
+
+#[tokio::test]
+async fn slashed_deposit_is_shared_out() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "forfeit";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let defaulter = &members[2];
+
+    for member in &members[..2] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(8).await;
+
+    let ix = env.ix_slash_defaulter(&members[0], name, &defaulter.pubkey());
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    for member in &members[..2] {
+        let ix = env.ix_close_group(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // The slashed 2 USDC is split three ways with the refunds
+    let refunds = [105_666_666, 102_666_666, 666_666];
+    for (member, refund) in members.iter().zip(refunds) {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_withdraw_deposit(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            env.token_balance(&member.token_account).await - before,
+            refund
+        );
+    }
+}
+
+#[tokio::test]
+async fn ejected_defaulter_is_replaced_by_paying_arrears() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "eject";
+    let members = env.setup_group(name, 100 * USDC, 7, 3).await;
+    let defaulter = &members[3];
+
+    for member in &members[..3] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    env.warp_seconds(1).await;
+    let ix = env.ix_slash_defaulter(&members[0], name, &defaulter.pubkey());
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let ix = env.ix_vote_to_eject(&members[0], name, &defaulter.pubkey());
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::NotEjectable,
+    );
+
+    env.warp_days(7).await;
+    let ix = env.ix_vote_to_eject(defaulter, name, &defaulter.pubkey());
+    assert_error(
+        env.process(&[ix], &[&defaulter.keypair]).await,
+        KooPaaError::CannotEjectSelf,
+    );
+
+    let ix = env.ix_vote_to_eject(&members[0], name, &defaulter.pubkey());
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    assert!(!env.group(name).await.participants[3].ejected);
+
+    let ix = env.ix_vote_to_eject(&members[0], name, &defaulter.pubkey());
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::AlreadyVotedToEject,
+    );
+
+    // Two of the three other members are a majority
+    let ix = env.ix_vote_to_eject(&members[1], name, &defaulter.pubkey());
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+    let group = env.group(name).await;
+    assert!(group.participants[3].ejected);
+
+    // The slashed deposit is forfeited with the seat
+    assert_eq!(group.participants[3].slashed, 0);
+    assert_eq!(group.penalty_pool, 2 * USDC);
+
+    let ix = env.ix_contribute(defaulter, name);
+    assert_error(
+        env.process(&[ix], &[&defaulter.keypair]).await,
+        KooPaaError::MemberEjected,
+    );
+
+    // The vacant seat paid nothing in, so the pot is only what the others put up
+    for member in &members[..3] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    let before = env.token_balance(&members[0].token_account).await;
+    let ix = env.ix_payout(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[0].token_account).await - before,
+        300 * USDC
+    );
+
+    let ix = env.ix_admit_replacement(&members[0], name, &defaulter.pubkey());
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::AlreadyJoined,
+    );
+
+    let replacement = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_admit_replacement(&replacement, name, &defaulter.pubkey());
+    env.process(&[ix], &[&replacement.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&replacement.token_account).await,
+        698 * USDC
+    );
+
+    let group = env.group(name).await;
+    let position = &group.participants[3];
+    assert_eq!(position.pubkey, replacement.pubkey());
+    assert_eq!(position.contribution_round, 3);
+    assert_eq!(position.security_deposit, 2 * USDC);
+    assert!(!position.defaulted && !position.ejected);
+    assert_eq!(group.penalty_pool, 2 * USDC);
+
+    // The round owed to the pot already paid out goes to its recipient
+    assert_eq!(group.participants[0].credit, 100 * USDC);
+
+    let latecomer = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_admit_replacement(&latecomer, name, &defaulter.pubkey());
+    assert_error(
+        env.process(&[ix], &[&latecomer.keypair]).await,
+        KooPaaError::PositionNotVacant,
+    );
+}
+
+#[tokio::test]
+async fn vacant_position_is_skipped_once_the_next_payout_is_due() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "vacancy";
+    let members = env.setup_group(name, 100 * USDC, 7, 3).await;
+    let defaulter = &members[1];
+    let others = [&members[0], &members[2], &members[3]];
+
+    for member in others {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    env.warp_seconds(1).await;
+    let ix = env.ix_slash_defaulter(&members[0], name, &defaulter.pubkey());
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    let ix = env.ix_payout(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    env.warp_days(7).await;
+    for voter in &others[..2] {
+        let ix = env.ix_vote_to_eject(voter, name, &defaulter.pubkey());
+        env.process(&[ix], &[&voter.keypair]).await.unwrap();
+    }
+    for member in others {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // The second pot belongs to the vacant seat, which nobody can be paid for
+    let ix = env.ix_payout(defaulter, name);
+    assert_error(
+        env.process(&[ix], &[&defaulter.keypair]).await,
+        KooPaaError::MemberDefaulted,
+    );
+
+    // A replacement has until the third payout falls due to take it over
+    let ix = env.ix_skip_vacant_position(&members[2], name);
+    assert_error(
+        env.process(&[ix], &[&members[2].keypair]).await,
+        KooPaaError::VacancyNotYetSkippable,
+    );
+
+    env.warp_days(7).await;
+    let ix = env.ix_skip_vacant_position(&members[2], name);
+    env.process(&[ix], &[&members[2].keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(group.payout_round, 2);
+    for index in [0, 2, 3] {
+        assert_eq!(group.participants[index].credit, 100 * USDC);
+    }
+
+    let ix = env.ix_skip_vacant_position(&members[2], name);
+    assert_error(
+        env.process(&[ix], &[&members[2].keypair]).await,
+        KooPaaError::PositionNotVacant,
+    );
+
+    let before = env.token_balance(&members[2].token_account).await;
+    let ix = env.ix_payout(&members[2], name);
+    env.process(&[ix], &[&members[2].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[2].token_account).await - before,
+        300 * USDC
+    );
+}
+
+#[tokio::test]
+async fn only_one_ejection_is_voted_on_at_a_time() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "eject-one";
+    let members = env.setup_group(name, 100 * USDC, 7, 3).await;
+    let defaulters = [&members[2], &members[3]];
+
+    for member in &members[..2] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    env.warp_seconds(1).await;
+    for defaulter in defaulters {
+        let ix = env.ix_slash_defaulter(&members[0], name, &defaulter.pubkey());
+        env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+
+    let ix = env.ix_vote_to_eject(&members[0], name, &defaulters[1].pubkey());
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let ix = env.ix_vote_to_eject(&members[1], name, &defaulters[0].pubkey());
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::EjectionInProgress,
+    );
+}
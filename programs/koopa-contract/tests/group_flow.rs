@@ -0,0 +1,48 @@
+mod common;
+
+use common::*;
+use koopa_contract::{state::FeeMode, utils::find_group_vault_pda};
+
+#[tokio::test]
+async fn create_join_contribute_payout_share_one_vault() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "flow";
+    let group_key = group_pda(name);
+    let vault = vault_pda(&group_key);
+
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    // Every deposit lands in the vault derived from the group address
+    assert_eq!(
+        vault,
+        find_group_vault_pda(&group_key, &koopa_contract::ID).0
+    );
+    assert_eq!(env.token_balance(&vault).await, 9 * USDC);
+
+    let group = env.group(name).await;
+    assert_eq!(group.participants.len(), 3);
+    assert!(group.start_timestamp.is_some());
+
+    for member in &members {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    assert_eq!(env.token_balance(&vault).await, 309 * USDC);
+    env.warp_days(7).await;
+
+    let recipient = &members[0];
+    let before = env.token_balance(&recipient.token_account).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+
+    // 1% protocol fee comes out of the 300 USDC pot
+    assert_eq!(
+        env.token_balance(&recipient.token_account).await - before,
+        297 * USDC
+    );
+    assert_eq!(env.token_balance(&vault).await, 9 * USDC);
+    assert_eq!(env.token_balance(&treasury_pda(&env.mint)).await, 3 * USDC);
+    assert_eq!(env.group(name).await.payout_round, 1);
+    let mint = env.mint;
+    assert_eq!(env.treasury_state(&mint).await.total_revenue, 3 * USDC);
+}
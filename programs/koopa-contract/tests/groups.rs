@@ -0,0 +1,602 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use koopa_contract::{
+    errors::KooPaaError,
+    state::{FeeMode, GroupStatus},
+};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn create_group_validates_params() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let creator = env.create_user(1_000 * USDC).await;
+
+    let cases = [
+        ((0, 7, 7, 3), KooPaaError::InvalidContributionAmount),
+        ((100 * USDC, 0, 7, 3), KooPaaError::InvalidInterval),
+        ((100 * USDC, 7, 91, 3), KooPaaError::InvalidInterval),
+        ((100 * USDC, 7, 10, 3), KooPaaError::InvalidInterval),
+        ((100 * USDC, 7, 7, 2), KooPaaError::InvalidParticipantCount),
+        ((100 * USDC, 7, 7, 21), KooPaaError::InvalidParticipantCount),
+        (
+            (100 * USDC, 1, 30, 10),
+            KooPaaError::TooManyContributionRounds,
+        ),
+    ];
+    for ((amount, contribution_interval, payout_interval, size), expected) in cases {
+        let ix = env.ix_create_group(
+            &creator,
+            "invalid",
+            amount,
+            contribution_interval,
+            payout_interval,
+            size,
+        );
+        assert_error(env.process(&[ix], &[&creator.keypair]).await, expected);
+    }
+}
+
+#[tokio::test]
+async fn group_name_fits_in_a_seed() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let creator = env.create_user(1_000 * USDC).await;
+
+    let name = "a".repeat(32);
+    let ix = env.ix_create_group(&creator, &name, 100 * USDC, 7, 7, 3);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+    assert_eq!(env.group(&name).await.name, name);
+
+    // A longer name has no group address, so only the name in the data is lengthened
+    let mut ix = env.ix_create_group(&creator, &"b".repeat(32), 100 * USDC, 7, 7, 3);
+    ix.data[8..12].copy_from_slice(&33u32.to_le_bytes());
+    ix.data.insert(12, b'b');
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::NameTooLong,
+    );
+}
+
+#[tokio::test]
+async fn group_starts_once_full() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let members = env.setup_partial_group("full", 100 * USDC, 7, 3, 1).await;
+
+    let group = env.group("full").await;
+    assert_eq!(group.participants.len(), 2);
+    assert_eq!(group.participants[0].security_deposit, 5 * USDC);
+    assert_eq!(group.participants[1].security_deposit, 2 * USDC);
+    assert!(group.start_timestamp.is_none());
+
+    let ix = env.ix_join(&members[1], "full");
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::AlreadyJoined,
+    );
+
+    let last = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&last, "full");
+    env.process(&[ix], &[&last.keypair]).await.unwrap();
+
+    let now = env.now().await;
+    assert_eq!(env.group("full").await.start_timestamp, Some(now));
+
+    let global_state = env.global_state().await;
+    assert_eq!(global_state.total_groups, 1);
+    assert_eq!(global_state.active_groups, 1);
+
+    let late = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&late, "full");
+    assert_error(
+        env.process(&[ix], &[&late.keypair]).await,
+        KooPaaError::GroupAlreadyStarted,
+    );
+}
+
+#[tokio::test]
+async fn unanimous_vote_starts_group_early() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "early";
+    let args = GroupArgs {
+        num_participants: 5,
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 2).await;
+
+    let outsider = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_vote_start_early(&outsider, name);
+    assert_error(
+        env.process(&[ix], &[&outsider.keypair]).await,
+        KooPaaError::NotParticipant,
+    );
+
+    for member in &members {
+        let ix = env.ix_vote_start_early(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    let group = env.group(name).await;
+    assert_eq!(group.num_participants, 3);
+    assert!(group.start_timestamp.is_some());
+    assert_eq!(env.global_state().await.active_groups, 1);
+
+    let ix = env.ix_join(&outsider, name);
+    assert_error(
+        env.process(&[ix], &[&outsider.keypair]).await,
+        KooPaaError::GroupAlreadyStarted,
+    );
+
+    // The pot is sized to the members who actually started
+    for member in &members {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    let recipient = &members[0];
+    let before = env.token_balance(&recipient.token_account).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&recipient.token_account).await - before,
+        300 * USDC
+    );
+}
+
+#[tokio::test]
+async fn start_early_needs_every_member_and_the_minimum_size() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let members = env.setup_partial_group("small", 100 * USDC, 7, 5, 1).await;
+
+    let ix = env.ix_vote_start_early(&members[0], "small");
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::InvalidParticipantCount,
+    );
+
+    let name = "waiting";
+    let members = env.setup_partial_group(name, 100 * USDC, 7, 5, 2).await;
+    for member in &members[..2] {
+        let ix = env.ix_vote_start_early(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    let ix = env.ix_vote_start_early(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::AlreadyVotedToStart,
+    );
+
+    // Someone joining mid-vote has to agree too
+    let joiner = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&joiner, name);
+    env.process(&[ix], &[&joiner.keypair]).await.unwrap();
+
+    let ix = env.ix_vote_start_early(&members[2], name);
+    env.process(&[ix], &[&members[2].keypair]).await.unwrap();
+    assert!(env.group(name).await.start_timestamp.is_none());
+
+    let ix = env.ix_vote_start_early(&joiner, name);
+    env.process(&[ix], &[&joiner.keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(group.num_participants, 4);
+    assert!(group.start_timestamp.is_some());
+}
+
+#[tokio::test]
+async fn start_vote_is_repeated_once_the_holdout_leaves() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "holdout";
+    let members = env.setup_partial_group(name, 100 * USDC, 7, 5, 3).await;
+    let holdout = &members[3];
+
+    for member in &members[..3] {
+        let ix = env.ix_vote_start_early(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    let ix = env.ix_leave(holdout, name);
+    env.process(&[ix], &[&holdout.keypair]).await.unwrap();
+
+    // Everyone left has already voted, so voting again starts the group
+    let ix = env.ix_vote_start_early(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(group.status, GroupStatus::Active);
+    assert_eq!(group.num_participants, 3);
+}
+
+#[tokio::test]
+async fn join_rejects_a_different_mint() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    env.setup_partial_group("mint", 100 * USDC, 7, 3, 0).await;
+
+    let other_mint = env.create_mint(DECIMALS).await;
+    let joiner = env.create_user(1_000 * USDC).await;
+    let mut ix = env.ix_join(&joiner, "mint");
+    replace_account(&mut ix, &env.mint, &other_mint);
+
+    assert_error(
+        env.process(&[ix], &[&joiner.keypair]).await,
+        KooPaaError::InvalidMint,
+    );
+}
+
+#[tokio::test]
+async fn members_can_leave_before_start() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "leave";
+    let members = env.setup_partial_group(name, 100 * USDC, 7, 3, 1).await;
+    let leaver = &members[1];
+
+    let ix = env.ix_leave(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::CreatorCannotLeave,
+    );
+
+    let before = env.token_balance(&leaver.token_account).await;
+    let ix = env.ix_leave(leaver, name);
+    env.process(&[ix], &[&leaver.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&leaver.token_account).await - before,
+        2 * USDC
+    );
+    assert_eq!(env.group(name).await.participants.len(), 1);
+
+    let ix = env.ix_leave(leaver, name);
+    assert_error(
+        env.process(&[ix], &[&leaver.keypair]).await,
+        KooPaaError::NotParticipant,
+    );
+
+    // The freed seat can be taken again, and nobody can leave once the group starts
+    for joiner in [leaver, &env.create_user(1_000 * USDC).await] {
+        let ix = env.ix_join(joiner, name);
+        env.process(&[ix], &[&joiner.keypair]).await.unwrap();
+    }
+    let ix = env.ix_leave(leaver, name);
+    assert_error(
+        env.process(&[ix], &[&leaver.keypair]).await,
+        KooPaaError::GroupAlreadyStarted,
+    );
+}
+
+#[tokio::test]
+async fn creator_can_cancel_an_unfilled_group() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "cancel";
+    let members = env.setup_partial_group(name, 100 * USDC, 7, 3, 1).await;
+
+    let ix = env.ix_cancel_group(&members[1], name);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::OnlyCreator,
+    );
+
+    let ix = env.ix_cancel_group(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    assert_eq!(env.group(name).await.status, GroupStatus::Cancelled);
+
+    let late = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&late, name);
+    assert_error(
+        env.process(&[ix], &[&late.keypair]).await,
+        KooPaaError::GroupAlreadyClosed,
+    );
+
+    for (member, refund) in members.iter().zip([5 * USDC, 2 * USDC]) {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_withdraw_deposit(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            env.token_balance(&member.token_account).await - before,
+            refund
+        );
+    }
+    assert_eq!(env.group(name).await.status, GroupStatus::Closed);
+    assert_eq!(env.global_state().await.active_groups, 0);
+}
+
+#[tokio::test]
+async fn unfilled_group_expires_after_join_deadline() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "expire";
+    let creator = env.create_user(1_000 * USDC).await;
+
+    let args = GroupArgs {
+        join_deadline: Some(env.now().await),
+        ..GroupArgs::default()
+    };
+    let ix = env.ix_create_group_with(&creator, name, &args);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::InvalidJoinDeadline,
+    );
+
+    let args = GroupArgs {
+        join_deadline: Some(env.now().await + 3 * DAY),
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 1).await;
+
+    let ix = env.ix_expire_group(name);
+    assert_error(
+        env.process(&[ix], &[]).await,
+        KooPaaError::JoinDeadlineNotReached,
+    );
+
+    env.warp_days(4).await;
+    let late = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&late, name);
+    assert_error(
+        env.process(&[ix], &[&late.keypair]).await,
+        KooPaaError::JoinDeadlinePassed,
+    );
+
+    // Anyone can expire the group once the deadline has passed
+    let ix = env.ix_expire_group(name);
+    env.process(&[ix], &[]).await.unwrap();
+    assert_eq!(env.group(name).await.status, GroupStatus::Cancelled);
+    assert_eq!(env.global_state().await.expired_groups, 1);
+
+    let ix = env.ix_expire_group(name);
+    assert_error(
+        env.process(&[ix], &[]).await,
+        KooPaaError::GroupAlreadyClosed,
+    );
+
+    for (member, refund) in members.iter().zip([5 * USDC, 2 * USDC]) {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_withdraw_deposit(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            env.token_balance(&member.token_account).await - before,
+            refund
+        );
+    }
+}
+
+#[tokio::test]
+async fn majority_vote_closes_group() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let members = env.setup_group("vote", 100 * USDC, 7, 2).await;
+    let outsider = env.create_user(1_000 * USDC).await;
+
+    let ix = env.ix_close_group(&outsider, "vote");
+    assert_error(
+        env.process(&[ix], &[&outsider.keypair]).await,
+        KooPaaError::NotParticipant,
+    );
+
+    let ix = env.ix_close_group(&members[0], "vote");
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    assert_eq!(env.group("vote").await.status, GroupStatus::Active);
+
+    let ix = env.ix_close_group(&members[0], "vote");
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::AlreadyVotedToClose,
+    );
+
+    let ix = env.ix_close_group(&members[1], "vote");
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+    assert_eq!(env.group("vote").await.status, GroupStatus::Settling);
+    assert_eq!(env.global_state().await.active_groups, 0);
+
+    let ix = env.ix_close_group(&members[2], "vote");
+    assert_error(
+        env.process(&[ix], &[&members[2].keypair]).await,
+        KooPaaError::GroupAlreadyClosed,
+    );
+}
+
+#[tokio::test]
+async fn deposits_are_refunded_after_close() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "refund";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let vault = vault_pda(&group_pda(name));
+
+    let ix = env.ix_withdraw_deposit(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::GroupNotSettled,
+    );
+
+    // Only the creator pays into round one before the group is voted closed
+    let ix = env.ix_contribute(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    for member in &members[..2] {
+        let ix = env.ix_close_group(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    let refunds = [105 * USDC, 2 * USDC, 2 * USDC];
+    for (member, refund) in members.iter().zip(refunds) {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_withdraw_deposit(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            env.token_balance(&member.token_account).await - before,
+            refund
+        );
+    }
+    assert_eq!(env.token_balance(&vault).await, 0);
+
+    let ix = env.ix_withdraw_deposit(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::AlreadyRefunded,
+    );
+}
+
+#[tokio::test]
+async fn deposits_are_refunded_after_final_payout() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "settled";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    for recipient in &members {
+        for member in &members {
+            let ix = env.ix_contribute(member, name);
+            env.process(&[ix], &[&member.keypair]).await.unwrap();
+        }
+        env.warp_days(7).await;
+        let ix = env.ix_payout(recipient, name);
+        env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    }
+    assert_eq!(env.group(name).await.payout_round, 3);
+
+    for member in &members {
+        let ix = env.ix_withdraw_deposit(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // Every member paid 300 and received 300, so only deposits moved
+    assert_eq!(
+        env.token_balance(&members[0].token_account).await,
+        10_000 * USDC
+    );
+    assert_eq!(
+        env.token_balance(&members[1].token_account).await,
+        10_000 * USDC
+    );
+    assert_eq!(env.token_balance(&vault_pda(&group_pda(name))).await, 0);
+}
+
+#[tokio::test]
+async fn legacy_group_migrates_to_the_current_layout() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "legacy";
+    let creator = env.create_user(1_000 * USDC).await;
+    let joiner = env.create_user(1_000 * USDC).await;
+    env.install_legacy_global_state(1).await;
+    env.install_legacy_group(name, &[&creator, &joiner], true)
+        .await;
+    env.install_legacy_vault(name, &creator.pubkey(), 7 * USDC)
+        .await;
+
+    // Groups cannot move over until the admin has upgraded the global state
+    let ix = env.ix_migrate_group_vault(&creator.pubkey(), name);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        ErrorCode::AccountDidNotDeserialize,
+    );
+    let admin = env.admin.insecure_clone();
+    let ix = env.ix_migrate_global_state(&admin.pubkey());
+    env.process(&[ix], &[&admin]).await.unwrap();
+
+    // Only the creator that owns the legacy vault can sign the move
+    let ix = env.ix_migrate_group_vault(&joiner.pubkey(), name);
+    assert_error(
+        env.process(&[ix], &[&joiner.keypair]).await,
+        KooPaaError::InvalidVaultAuthority,
+    );
+
+    let ix = env.ix_migrate_group_vault(&creator.pubkey(), name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    assert_eq!(
+        env.token_balance(&vault_pda(&group_pda(name))).await,
+        7 * USDC
+    );
+    let legacy_vault = env
+        .ctx
+        .banks_client
+        .get_account(legacy_vault_pda(name))
+        .await
+        .unwrap();
+    assert!(legacy_vault.is_none());
+
+    let group = env.group(name).await;
+    assert_eq!(group.creator, creator.pubkey());
+    assert_eq!(group.token_mint, env.mint);
+    assert_eq!(group.status, GroupStatus::Active);
+    assert_eq!(group.participants.len(), 2);
+    assert_eq!(group.participants[0].security_deposit, 5 * USDC);
+    assert_eq!(group.participants[1].security_deposit, 2 * USDC);
+
+    // The migrated group runs like any other
+    for member in [&creator, &joiner] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    let before = env.token_balance(&creator.token_account).await;
+    let ix = env.ix_payout(&creator, name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&creator.token_account).await - before,
+        198 * USDC
+    );
+}
+
+#[tokio::test]
+async fn legacy_recruiting_group_waits_for_the_size_asked_for() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "legacy-open";
+    let creator = env.create_user(1_000 * USDC).await;
+    env.install_legacy_group(name, &[&creator], false).await;
+    env.install_legacy_vault(name, &creator.pubkey(), 5 * USDC)
+        .await;
+
+    let ix = env.ix_migrate_group_vault(&creator.pubkey(), name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    // The first release would have started with two members, one short of the three asked for
+    let group = env.group(name).await;
+    assert_eq!(group.status, GroupStatus::Recruiting);
+    assert_eq!(group.num_participants, 3);
+
+    let joiner = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_join(&joiner, name);
+    env.process(&[ix], &[&joiner.keypair]).await.unwrap();
+    assert_eq!(env.group(name).await.status, GroupStatus::Recruiting);
+}
+
+#[tokio::test]
+async fn legacy_group_intervals_are_checked_on_migration() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let creator = env.create_user(1_000 * USDC).await;
+    let joiner = env.create_user(1_000 * USDC).await;
+
+    // A zero interval can never be scheduled, so the group stays where it is
+    env.install_legacy_group_with("zero", &[&creator, &joiner], true, (0, 7))
+        .await;
+    env.install_legacy_vault("zero", &creator.pubkey(), 7 * USDC)
+        .await;
+    let ix = env.ix_migrate_group_vault(&creator.pubkey(), "zero");
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::InvalidInterval,
+    );
+
+    // A payout interval that is not a whole number of contribution rounds falls back to
+    // one contribution per payout, as the first release charged it
+    let name = "uneven";
+    env.install_legacy_group_with(name, &[&creator, &joiner], true, (7, 10))
+        .await;
+    env.install_legacy_vault(name, &creator.pubkey(), 7 * USDC)
+        .await;
+    let ix = env.ix_migrate_group_vault(&creator.pubkey(), name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert_eq!(group.contribution_interval, 10);
+    assert_eq!(group.payout_interval, 10);
+
+    for member in [&creator, &joiner] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(10).await;
+    let before = env.token_balance(&creator.token_account).await;
+    let ix = env.ix_payout(&creator, name);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&creator.token_account).await - before,
+        198 * USDC
+    );
+}
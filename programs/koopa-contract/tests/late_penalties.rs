@@ -0,0 +1,128 @@
+mod common;
+
+use common::*;
+use koopa_contract::{errors::KooPaaError, state::*};
+
+#[tokio::test]
+async fn create_group_validates_late_policy() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let creator = env.create_user(1_000 * USDC).await;
+
+    for penalty in [
+        LatePenalty::Flat(0),
+        LatePenalty::BpsPerDay(0),
+        LatePenalty::BpsPerDay(10_001),
+    ] {
+        let args = GroupArgs {
+            late_policy: LatePolicy {
+                penalty,
+                ..LatePolicy::NONE
+            },
+            ..GroupArgs::default()
+        };
+        let ix = env.ix_create_group_with(&creator, "invalid", &args);
+        assert_error(
+            env.process(&[ix], &[&creator.keypair]).await,
+            KooPaaError::InvalidLatePolicy,
+        );
+    }
+}
+
+#[tokio::test]
+async fn late_penalty_goes_to_the_current_recipient() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "flat";
+    let args = GroupArgs {
+        late_policy: LatePolicy {
+            grace_period_hours: 12,
+            penalty: LatePenalty::Flat(5 * USDC),
+            destination: PenaltyDestination::CurrentRecipient,
+        },
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 2).await;
+
+    // Paying inside the grace period costs nothing extra; round two has opened
+    // by then and is paid along with it
+    env.warp_days(7).await;
+    env.warp_seconds(6 * 60 * 60).await;
+    let before = env.token_balance(&members[0].token_account).await;
+    let ix = env.ix_contribute(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    assert_eq!(
+        before - env.token_balance(&members[0].token_account).await,
+        200 * USDC
+    );
+
+    env.warp_days(1).await;
+    for member in &members[1..] {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            before - env.token_balance(&member.token_account).await,
+            205 * USDC
+        );
+    }
+    assert_eq!(env.group(name).await.round_bonus, 10 * USDC);
+
+    let recipient = &members[0];
+    let before = env.token_balance(&recipient.token_account).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&recipient.token_account).await - before,
+        310 * USDC
+    );
+    assert_eq!(env.group(name).await.round_bonus, 0);
+}
+
+#[tokio::test]
+async fn late_penalty_is_pooled_until_refunds() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "pooled";
+    let args = GroupArgs {
+        late_policy: LatePolicy {
+            grace_period_hours: 24,
+            penalty: LatePenalty::BpsPerDay(100),
+            destination: PenaltyDestination::Vault,
+        },
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 2).await;
+
+    for member in &members[..2] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // Two days past the due time is 1% of the contribution for each day; the
+    // round that has opened since is not late
+    env.warp_days(9).await;
+    let late = &members[2];
+    let before = env.token_balance(&late.token_account).await;
+    let ix = env.ix_contribute(late, name);
+    env.process(&[ix], &[&late.keypair]).await.unwrap();
+    assert_eq!(
+        before - env.token_balance(&late.token_account).await,
+        202 * USDC
+    );
+    assert_eq!(env.group(name).await.penalty_pool, 2 * USDC);
+
+    for member in &members[..2] {
+        let ix = env.ix_close_group(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    let share = 2 * USDC / 3;
+    let refunds = [105 * USDC + share, 102 * USDC + share, 202 * USDC + share];
+    for (member, refund) in members.iter().zip(refunds) {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_withdraw_deposit(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            env.token_balance(&member.token_account).await - before,
+            refund
+        );
+    }
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use common::*;
+use koopa_contract::{
+    errors::KooPaaError,
+    state::{AjoGroup, FeeMode, PayoutOrder},
+    utils::shuffle,
+};
+
+fn claim_rounds(group: &AjoGroup) -> Vec<u8> {
+    group.participants.iter().map(|p| p.claim_round).collect()
+}
+
+// Replays the shuffle from the recorded seed, the way any member can verify it
+fn replay_claim_rounds(group: &AjoGroup) -> Vec<u8> {
+    let mut order: Vec<usize> = (0..group.participants.len()).collect();
+    shuffle(&mut order, &group.payout_seed.unwrap());
+
+    let mut claim_rounds = vec![0; order.len()];
+    for (claim_round, index) in order.into_iter().enumerate() {
+        claim_rounds[index] = claim_round as u8;
+    }
+    claim_rounds
+}
+
+#[tokio::test]
+async fn join_order_pays_members_as_they_joined() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    env.setup_group("joined", 100 * USDC, 7, 2).await;
+
+    let group = env.group("joined").await;
+    assert_eq!(group.payout_order, PayoutOrder::JoinOrder);
+    assert_eq!(claim_rounds(&group), vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn fixed_order_follows_the_creator_list() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "fixed";
+    let args = GroupArgs {
+        payout_order: PayoutOrder::Fixed,
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 0).await;
+    let creator = &members[0];
+    let first = env.create_user(10_000 * USDC).await;
+    let second = env.create_user(10_000 * USDC).await;
+
+    let ix = env.ix_set_payout_order(&first, name, vec![first.pubkey()]);
+    assert_error(
+        env.process(&[ix], &[&first.keypair]).await,
+        KooPaaError::OnlyCreator,
+    );
+
+    let ix = env.ix_set_payout_order(creator, name, vec![first.pubkey(), first.pubkey()]);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::DuplicatePayoutPosition,
+    );
+
+    // The creator is left unlisted, so they are paid after both listed members
+    let ix = env.ix_set_payout_order(creator, name, vec![second.pubkey(), first.pubkey()]);
+    env.process(&[ix], &[&creator.keypair]).await.unwrap();
+
+    for joiner in [&first, &second] {
+        let ix = env.ix_join(joiner, name);
+        env.process(&[ix], &[&joiner.keypair]).await.unwrap();
+    }
+    assert_eq!(claim_rounds(&env.group(name).await), vec![2, 1, 0]);
+
+    let ix = env.ix_set_payout_order(creator, name, vec![]);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::GroupAlreadyStarted,
+    );
+
+    let ix = env.ix_place_payout_bid(creator, name, USDC);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::PayoutOrderMismatch,
+    );
+
+    for member in [creator, &first, &second] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+
+    let ix = env.ix_payout(creator, name);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::NotCurrentRecipient,
+    );
+
+    let ix = env.ix_payout(&second, name);
+    env.process(&[ix], &[&second.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn random_order_is_a_permutation() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let args = GroupArgs {
+        num_participants: 6,
+        payout_order: PayoutOrder::Random,
+        ..GroupArgs::default()
+    };
+    env.setup_group_with("random", &args, 5).await;
+
+    let group = env.group("random").await;
+    assert_eq!(claim_rounds(&group), replay_claim_rounds(&group));
+
+    let mut rounds = claim_rounds(&group);
+    rounds.sort();
+    assert_eq!(rounds, vec![0, 1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn verifiable_random_order_is_revealed_after_the_commit_slot() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "verifiable";
+    let args = GroupArgs {
+        num_participants: 5,
+        payout_order: PayoutOrder::VerifiableRandom,
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 4).await;
+
+    let group = env.group(name).await;
+    assert!(group.start_timestamp.is_some());
+    assert_eq!(group.payout_seed, None);
+
+    let ix = env.ix_reveal_payout_order(name);
+    assert_error(env.process(&[ix], &[]).await, KooPaaError::RevealNotReady);
+
+    // Nobody can be paid while the order is still unknown
+    for member in &members {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    let ix = env.ix_payout(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::PayoutOrderNotRevealed,
+    );
+
+    env.warp_slots(AjoGroup::REVEAL_DELAY_SLOTS + 1).await;
+    let ix = env.ix_reveal_payout_order(name);
+    env.process(&[ix], &[]).await.unwrap();
+
+    let group = env.group(name).await;
+    assert!(group.payout_seed.is_some());
+    assert_eq!(claim_rounds(&group), replay_claim_rounds(&group));
+
+    let ix = env.ix_reveal_payout_order(name);
+    assert_error(
+        env.process(&[ix], &[]).await,
+        KooPaaError::PayoutOrderAlreadyRevealed,
+    );
+
+    let first = group
+        .participants
+        .iter()
+        .position(|p| p.claim_round == 0)
+        .unwrap();
+    let ix = env.ix_payout(&members[first], name);
+    env.process(&[ix], &[&members[first].keypair])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn reveal_needs_slot_hashes() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "no-hashes";
+    let args = GroupArgs {
+        payout_order: PayoutOrder::VerifiableRandom,
+        ..GroupArgs::default()
+    };
+    env.setup_group_with(name, &args, 2).await;
+
+    env.warp_slots(AjoGroup::REVEAL_DELAY_SLOTS + 1).await;
+    env.clear_slot_hashes().await;
+    let ix = env.ix_reveal_payout_order(name);
+    assert_error(
+        env.process(&[ix], &[]).await,
+        KooPaaError::SlotHashUnavailable,
+    );
+}
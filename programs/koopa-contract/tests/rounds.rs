@@ -0,0 +1,393 @@
+mod common;
+
+use common::*;
+use koopa_contract::{
+    errors::KooPaaError,
+    state::{FeeMode, GroupStatus, LatePenalty, LatePolicy},
+};
+
+#[tokio::test]
+async fn contribute_requires_a_started_group_and_an_open_round() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "rounds";
+    let members = env.setup_partial_group(name, 100 * USDC, 7, 3, 1).await;
+
+    let ix = env.ix_contribute(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::GroupNotStarted,
+    );
+
+    let last = env.create_user(10 * USDC).await;
+    let ix = env.ix_join(&last, name);
+    env.process(&[ix], &[&last.keypair]).await.unwrap();
+
+    // Round one opens as soon as the group starts, round two only a week later
+    let ix = env.ix_contribute(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let ix = env.ix_contribute(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::AlreadyContributed,
+    );
+
+    // The last joiner only holds 8 USDC after paying their deposit
+    let ix = env.ix_contribute(&last, name);
+    assert_error(
+        env.process(&[ix], &[&last.keypair]).await,
+        KooPaaError::InsufficientFunds,
+    );
+
+    let outsider = env.create_user(1_000 * USDC).await;
+    let ix = env.ix_contribute(&outsider, name);
+    assert_error(
+        env.process(&[ix], &[&outsider.keypair]).await,
+        KooPaaError::NotParticipant,
+    );
+}
+
+#[tokio::test]
+async fn contribute_catches_up_missed_rounds() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "catch-up";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let vault = vault_pda(&group_pda(name));
+
+    env.warp_days(14).await;
+    let ix = env.ix_contribute(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+
+    assert_eq!(env.token_balance(&vault).await, 309 * USDC);
+    assert_eq!(env.group(name).await.participants[1].contribution_round, 3);
+}
+
+#[tokio::test]
+async fn prepaid_rounds_are_refunded_if_the_group_closes_early() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "prepaid";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let traveller = &members[1];
+
+    for (rounds, expected) in [
+        (0, KooPaaError::InvalidPrepayment),
+        (3, KooPaaError::PrepaymentExceedsCycle),
+        (u8::MAX, KooPaaError::PrepaymentExceedsCycle),
+    ] {
+        let ix = env.ix_contribute_ahead(traveller, name, rounds);
+        assert_error(env.process(&[ix], &[&traveller.keypair]).await, expected);
+    }
+
+    // The open round plus one ahead
+    let before = env.token_balance(&traveller.token_account).await;
+    let ix = env.ix_contribute_ahead(traveller, name, 1);
+    env.process(&[ix], &[&traveller.keypair]).await.unwrap();
+    assert_eq!(
+        before - env.token_balance(&traveller.token_account).await,
+        200 * USDC
+    );
+
+    for member in [&members[0], &members[2]] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    let ix = env.ix_payout(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    // The prepaid round covers round two without another contribution
+    let ix = env.ix_contribute(traveller, name);
+    assert_error(
+        env.process(&[ix], &[&traveller.keypair]).await,
+        KooPaaError::AlreadyContributed,
+    );
+
+    for member in &members[..2] {
+        let ix = env.ix_close_group(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    // The round paid for but never paid out comes back with the deposit
+    let before = env.token_balance(&traveller.token_account).await;
+    let ix = env.ix_withdraw_deposit(traveller, name);
+    env.process(&[ix], &[&traveller.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&traveller.token_account).await - before,
+        102 * USDC
+    );
+}
+
+#[tokio::test]
+async fn claim_round_checks_eligibility() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "claim";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    let ix = env.ix_contribute(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    env.warp_days(7).await;
+    let ix = env.ix_claim_round(&members[1], name);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::NotCurrentRecipient,
+    );
+
+    let ix = env.ix_claim_round(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::NotAllContributed,
+    );
+
+    for member in &members[1..] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+
+    let ix = env.ix_claim_round(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn payout_goes_to_the_current_recipient_once_due() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "payout";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    let ix = env.ix_payout(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::PayoutNotYetDue,
+    );
+
+    // A fully funded pot still waits for its payout date
+    for member in &members {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    let ix = env.ix_payout(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::PayoutNotYetDue,
+    );
+
+    env.warp_days(7).await;
+    let ix = env.ix_payout(&members[1], name);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::NotCurrentRecipient,
+    );
+
+    let ix = env.ix_payout(&members[0], name);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+
+    let ix = env.ix_payout(&members[1], name);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::PayoutNotYetDue,
+    );
+}
+
+#[tokio::test]
+async fn payout_waits_for_every_member_to_pay_in() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "unfunded";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (recipient, holdout) = (&members[0], &members[2]);
+
+    // The prepaid round and the deposits in the vault would cover the pot, but only
+    // the holdout's own contribution can
+    let ix = env.ix_contribute_ahead(recipient, name, 1);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    let ix = env.ix_contribute(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+
+    env.warp_days(7).await;
+    let ix = env.ix_payout(recipient, name);
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::NotAllContributed,
+    );
+
+    let ix = env.ix_contribute(holdout, name);
+    env.process(&[ix], &[&holdout.keypair]).await.unwrap();
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+}
+
+#[tokio::test]
+async fn fee_on_contribution_is_charged_on_top() {
+    let mut env = TestEnv::new(10, FeeMode::OnContribution).await;
+    let name = "fee";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let vault = vault_pda(&group_pda(name));
+    let contributor = &members[1];
+
+    let before = env.token_balance(&contributor.token_account).await;
+    let ix = env.ix_contribute(contributor, name);
+    env.process(&[ix], &[&contributor.keypair]).await.unwrap();
+
+    assert_eq!(
+        before - env.token_balance(&contributor.token_account).await,
+        101 * USDC
+    );
+    assert_eq!(env.token_balance(&vault).await, 109 * USDC);
+    assert_eq!(env.token_balance(&treasury_pda(&env.mint)).await, USDC);
+    let mint = env.mint;
+    assert_eq!(env.treasury_state(&mint).await.total_revenue, USDC);
+
+    for member in [&members[0], &members[2]] {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+
+    // No second fee is taken from the pot at payout
+    let recipient = &members[0];
+    let before = env.token_balance(&recipient.token_account).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&recipient.token_account).await - before,
+        300 * USDC
+    );
+}
+
+#[tokio::test]
+async fn transfer_fee_mints_credit_the_full_amount() {
+    let transfer_fee = TransferFee {
+        basis_points: 100,
+        maximum_fee: 1_000 * USDC,
+    };
+    let mut env = TestEnv::with_transfer_fee(0, FeeMode::OnPayout, transfer_fee).await;
+    let name = "token-2022";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let vault = vault_pda(&group_pda(name));
+
+    // Deposits are grossed up so the vault holds exactly what was required
+    let group = env.group(name).await;
+    assert_eq!(group.participants[0].security_deposit, 5 * USDC);
+    assert_eq!(group.participants[1].security_deposit, 2 * USDC);
+    assert_eq!(env.token_balance(&vault).await, 9 * USDC);
+
+    let contributor = &members[1];
+    let before = env.token_balance(&contributor.token_account).await;
+    let ix = env.ix_contribute(contributor, name);
+    env.process(&[ix], &[&contributor.keypair]).await.unwrap();
+
+    assert!(before - env.token_balance(&contributor.token_account).await > 100 * USDC);
+    assert_eq!(env.token_balance(&vault).await, 109 * USDC);
+    assert_eq!(env.group(name).await.participants[1].contribution_round, 1);
+}
+
+#[tokio::test]
+async fn each_pot_collects_every_contribution_round_due_with_it() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "fortnightly";
+    let args = GroupArgs {
+        contribution_interval: 7,
+        payout_interval: 14,
+        late_policy: LatePolicy {
+            penalty: LatePenalty::Flat(USDC),
+            ..LatePolicy::NONE
+        },
+        ..GroupArgs::default()
+    };
+    let members = env.setup_group_with(name, &args, 2).await;
+    let recipient = &members[0];
+
+    let ix = env.ix_contribute(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+
+    // The first week's round is not late until the pot it funds is due
+    env.warp_days(7).await;
+    for (member, paid) in members.iter().zip([200 * USDC, 100 * USDC, 200 * USDC]) {
+        let before = env.token_balance(&member.token_account).await;
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+        assert_eq!(
+            before - env.token_balance(&member.token_account).await,
+            paid
+        );
+    }
+
+    let ix = env.ix_payout(recipient, name);
+    assert_error(
+        env.process(&[ix], &[&recipient.keypair]).await,
+        KooPaaError::PayoutNotYetDue,
+    );
+
+    env.warp_days(7).await;
+    let ix = env.ix_claim_round(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+
+    let before = env.token_balance(&recipient.token_account).await;
+    let ix = env.ix_payout(recipient, name);
+    env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&recipient.token_account).await - before,
+        600 * USDC
+    );
+}
+
+#[tokio::test]
+async fn group_completes_after_the_last_member_is_paid() {
+    let mut env = TestEnv::new(0, FeeMode::OnPayout).await;
+    let name = "cycle";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    for recipient in &members {
+        for member in &members {
+            let ix = env.ix_contribute(member, name);
+            env.process(&[ix], &[&member.keypair]).await.unwrap();
+        }
+        env.warp_days(7).await;
+        let ix = env.ix_payout(recipient, name);
+        env.process(&[ix], &[&recipient.keypair]).await.unwrap();
+    }
+
+    let group = env.group(name).await;
+    assert_eq!(group.status, GroupStatus::Completed);
+    for participant in &group.participants {
+        assert_eq!(participant.total_contributed, 300 * USDC);
+        assert_eq!(participant.total_received, 300 * USDC);
+    }
+    let global_state = env.global_state().await;
+    assert_eq!(global_state.active_groups, 0);
+    assert_eq!(global_state.completed_groups, 1);
+
+    // Nothing wraps around into a second cycle
+    env.warp_days(7).await;
+    let ix = env.ix_contribute(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::GroupCompleted,
+    );
+    let ix = env.ix_payout(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::GroupCompleted,
+    );
+    let ix = env.ix_close_group(&members[0], name);
+    assert_error(
+        env.process(&[ix], &[&members[0].keypair]).await,
+        KooPaaError::GroupCompleted,
+    );
+}
+
+#[tokio::test]
+async fn transfer_fee_that_cannot_be_covered_is_rejected() {
+    // Every transferred token is taken as a fee with no cap, so no gross-up lands
+    let transfer_fee = TransferFee {
+        basis_points: 10_000,
+        maximum_fee: u64::MAX,
+    };
+    let mut env = TestEnv::with_transfer_fee(0, FeeMode::OnPayout, transfer_fee).await;
+    let creator = env.create_user(1_000 * USDC).await;
+
+    let ix = env.ix_create_group(&creator, "all-fee", 100 * USDC, 7, 7, 3);
+    assert_error(
+        env.process(&[ix], &[&creator.keypair]).await,
+        KooPaaError::TransferFeeShortfall,
+    );
+}
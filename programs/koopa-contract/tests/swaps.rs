@@ -0,0 +1,112 @@
+mod common;
+
+use common::*;
+use koopa_contract::{errors::KooPaaError, state::FeeMode};
+
+#[tokio::test]
+async fn accepted_swap_trades_positions_and_pays_the_sweetener() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "swap";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+    let (first, last) = (&members[0], &members[2]);
+    let outsider = env.create_user(1_000 * USDC).await;
+
+    let ix = env.ix_propose_swap(last, &last.pubkey(), name, 0);
+    assert_error(
+        env.process(&[ix], &[&last.keypair]).await,
+        KooPaaError::CannotSwapWithSelf,
+    );
+
+    let ix = env.ix_propose_swap(last, &outsider.pubkey(), name, 0);
+    assert_error(
+        env.process(&[ix], &[&last.keypair]).await,
+        KooPaaError::NotParticipant,
+    );
+
+    // The last member pays 10 USDC to be paid first
+    let ix = env.ix_propose_swap(last, &first.pubkey(), name, 10 * USDC);
+    env.process(&[ix], &[&last.keypair]).await.unwrap();
+
+    let proposal = swap_pda(&group_pda(name), &last.pubkey());
+    assert_eq!(
+        env.token_balance(&swap_escrow_pda(&proposal)).await,
+        10 * USDC
+    );
+
+    // Only the named counterparty can accept
+    let ix = env.ix_accept_swap(&members[1], &last.pubkey(), name);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::NotSwapCounterparty,
+    );
+
+    let before = env.token_balance(&first.token_account).await;
+    let ix = env.ix_accept_swap(first, &last.pubkey(), name);
+    env.process(&[ix], &[&first.keypair]).await.unwrap();
+
+    assert_eq!(
+        env.token_balance(&first.token_account).await - before,
+        10 * USDC
+    );
+    let claim_rounds: Vec<u8> = env
+        .group(name)
+        .await
+        .participants
+        .iter()
+        .map(|p| p.claim_round)
+        .collect();
+    assert_eq!(claim_rounds, vec![2, 1, 0]);
+
+    let banks = &mut env.ctx.banks_client;
+    assert!(banks.get_account(proposal).await.unwrap().is_none());
+    assert!(banks
+        .get_account(swap_escrow_pda(&proposal))
+        .await
+        .unwrap()
+        .is_none());
+
+    for member in &members {
+        let ix = env.ix_contribute(member, name);
+        env.process(&[ix], &[&member.keypair]).await.unwrap();
+    }
+    env.warp_days(7).await;
+    let ix = env.ix_payout(last, name);
+    env.process(&[ix], &[&last.keypair]).await.unwrap();
+
+    // A paid position can never be traded again
+    let ix = env.ix_propose_swap(&members[1], &last.pubkey(), name, 0);
+    assert_error(
+        env.process(&[ix], &[&members[1].keypair]).await,
+        KooPaaError::PositionAlreadyPaid,
+    );
+}
+
+#[tokio::test]
+async fn stale_swap_is_rejected_and_can_be_cancelled() {
+    let mut env = TestEnv::new(10, FeeMode::OnPayout).await;
+    let name = "stale";
+    let members = env.setup_group(name, 100 * USDC, 7, 2).await;
+
+    let ix = env.ix_propose_swap(&members[1], &members[2].pubkey(), name, 5 * USDC);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+
+    // Another trade moves the counterparty before the first offer is accepted
+    let ix = env.ix_propose_swap(&members[0], &members[2].pubkey(), name, 0);
+    env.process(&[ix], &[&members[0].keypair]).await.unwrap();
+    let ix = env.ix_accept_swap(&members[2], &members[0].pubkey(), name);
+    env.process(&[ix], &[&members[2].keypair]).await.unwrap();
+
+    let ix = env.ix_accept_swap(&members[2], &members[1].pubkey(), name);
+    assert_error(
+        env.process(&[ix], &[&members[2].keypair]).await,
+        KooPaaError::SwapPositionsChanged,
+    );
+
+    let before = env.token_balance(&members[1].token_account).await;
+    let ix = env.ix_cancel_swap(&members[1], name);
+    env.process(&[ix], &[&members[1].keypair]).await.unwrap();
+    assert_eq!(
+        env.token_balance(&members[1].token_account).await - before,
+        5 * USDC
+    );
+}